@@ -0,0 +1,92 @@
+//! Compares the bitmask-based `Sudoku` with scanning the board, which is how
+//! conflicts, candidates and completion used to be computed.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sudoku_tui::generator::Generator;
+use sudoku_tui::solver::rate;
+use sudoku_tui::sudoku::{Coord, Sudoku, SudokuMatrix};
+
+const PUZZLE: &str =
+    "..9748...7.........2.1.9.....7...24..64.1.59..98...3.....8.3.2.........6...2759..";
+
+/// The previous implementation, which scans the matrix on every query.
+struct Scanning(SudokuMatrix);
+
+impl Scanning {
+    fn conflict(&self, v: u8, [i, j]: Coord) -> Option<Coord> {
+        let row = (0..9).map(|x| [i, x]);
+        let col = (0..9).map(|x| [x, j]);
+        let box_ = (0..9).map(|x| [i / 3 * 3 + x / 3, j / 3 * 3 + x % 3]);
+        row.chain(col).chain(box_).find(|&[a, b]| self.0[a][b] == v)
+    }
+
+    fn candidates(&self, coord: Coord) -> u16 {
+        if self.0[coord[0]][coord[1]] != 0 {
+            return 0;
+        }
+        let mut candidates = 0b11_1111_1110;
+        for [i, j] in Sudoku::peers(coord) {
+            candidates &= !(1 << self.0[i][j]);
+        }
+        candidates
+    }
+
+    fn finished(&self) -> bool {
+        self.0.iter().flatten().all(|&v| v != 0)
+    }
+}
+
+fn cells() -> impl Iterator<Item = Coord> {
+    (0..81).map(|x| [x / 9, x % 9])
+}
+
+fn queries(c: &mut Criterion) {
+    let sudoku: Sudoku = PUZZLE.parse().unwrap();
+    let scanning = Scanning(sudoku.matrix());
+
+    let mut group = c.benchmark_group("conflict");
+    group.bench_function("bitmask", |b| {
+        b.iter(|| {
+            cells()
+                .filter(|&c| black_box(&sudoku).conflict(5, c).is_some())
+                .count()
+        })
+    });
+    group.bench_function("scanning", |b| {
+        b.iter(|| {
+            cells()
+                .filter(|&c| black_box(&scanning).conflict(5, c).is_some())
+                .count()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("candidates");
+    group.bench_function("bitmask", |b| {
+        b.iter(|| cells().fold(0, |acc, c| acc ^ black_box(&sudoku).candidates(c)))
+    });
+    group.bench_function("scanning", |b| {
+        b.iter(|| cells().fold(0, |acc, c| acc ^ black_box(&scanning).candidates(c)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("finished");
+    group.bench_function("bitmask", |b| b.iter(|| black_box(&sudoku).finished()));
+    group.bench_function("scanning", |b| b.iter(|| black_box(&scanning).finished()));
+    group.finish();
+}
+
+fn rating(c: &mut Criterion) {
+    let sudoku: Sudoku = PUZZLE.parse().unwrap();
+    c.bench_function("rate", |b| b.iter(|| rate(black_box(&sudoku))));
+}
+
+fn generation(c: &mut Criterion) {
+    let mut generator = Generator::new().seed(0);
+    c.bench_function("generate", |b| b.iter(|| generator.generate()));
+}
+
+criterion_group!(benches, queries, rating, generation);
+criterion_main!(benches);
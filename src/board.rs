@@ -3,133 +3,229 @@
 // This software is released under the MIT License.
 // https://opensource.org/licenses/MIT
 
-use crate::sudoku::Sudoku;
-use clock_core::stopwatch::Stopwatch;
+//! The terminal frontend of the [`Game`] engine.
+
+use crate::coop::{Edit, Partner, State as CoopState};
+use crate::engine::{Action, Conflict, Direction, Editor, Event as GameEvent, Game, Settings};
+use crate::generator::{Generator, Puzzle, Solutions, Symmetry};
+use crate::history::{Bookmark, Command};
+use crate::pack::{Pack, Progress};
+use crate::print::{Item, Sheet};
+use crate::race::Racer;
+use crate::save::{SaveError, SavedGame};
+use crate::solver::Rating;
+use crate::sudoku::{Coord, Unit};
+use crate::versus::{Placement, Versus};
 use cursive::{
-    event::{Event, EventResult, Key, MouseEvent},
-    theme::ColorStyle,
+    event::{Event, EventResult, Key, MouseButton, MouseEvent},
+    theme::{BaseColor, Color, ColorStyle, Effect, PaletteColor},
     view::View,
     Printer, Vec2,
 };
 use hhmmss::Hhmmss;
-use rand::prelude::*;
-// type SudokuMatrix = [u8; 81];
-type SudokuMatrix = [[u8; 9]; 9];
 
-#[derive(Debug)]
+/// Colors of the players of two-player and cooperative games
+const PLAYER_COLORS: [BaseColor; 6] = [
+    BaseColor::Blue,
+    BaseColor::Red,
+    BaseColor::Green,
+    BaseColor::Magenta,
+    BaseColor::Cyan,
+    BaseColor::Yellow,
+];
+
+/// Backgrounds of the colors cells can be marked with, from color 1
+const CELL_COLORS: [BaseColor; 6] = [
+    BaseColor::Yellow,
+    BaseColor::Cyan,
+    BaseColor::Green,
+    BaseColor::Magenta,
+    BaseColor::Red,
+    BaseColor::Blue,
+];
+
+/// Row of the digit palette, below the status line
+const PALETTE_ROW: usize = 16;
+
+/// Row of the digit tracker, below the palette
+const TRACKER_ROW: usize = 17;
+
+/// A button of the digit palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaletteKey {
+    Digit(u8),
+    Erase,
+    /// Switch between answers and pencil marks
+    Note,
+    /// Switch between answers and colors
+    Color,
+}
+
+impl PaletteKey {
+    /// The button drawn at column `x` of `1 2 3 4 5 6 7 8 9 Del Note Color`
+    // see `Shape::with_size` about `is_multiple_of`
+    #[allow(unknown_lints, clippy::manual_is_multiple_of)]
+    fn at(x: usize) -> Option<Self> {
+        match x {
+            0..=16 if x % 2 == 0 => Some(Self::Digit(x as u8 / 2 + 1)),
+            18..=20 => Some(Self::Erase),
+            22..=25 => Some(Self::Note),
+            27..=31 => Some(Self::Color),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 enum BoardState {
     Config,
+    /// Typing in a puzzle
+    Editor,
     Playing,
     Finish,
 }
 
 #[derive(Debug)]
 pub struct SudokuBoard {
-    ans: SudokuMatrix,
-    sudoku: Sudoku,
-    focus: [usize; 2],
-    history: Vec<([usize; 2], u8)>,
-    redo: Vec<([usize; 2], u8)>,
-    undos: usize,
-    moves: usize,
-    hints: usize,
-    conflict: Option<[usize; 2]>,
+    game: Game,
+    /// Whether digits entered are pencil marks rather than answers
+    note_mode: bool,
+    /// Whether digits entered color the selected cells rather than fill them
+    color_mode: bool,
+    /// Digit whose occurrences are highlighted, chosen in the digit tracker
+    highlighted: Option<u8>,
+    pack: Option<Pack>,
+    /// The puzzle of `pack` being played
+    pack_index: usize,
+    progress: Progress,
+    /// Pattern of the clues of random puzzles
+    symmetry: Symmetry,
+    editor: Editor,
+    /// The screen the editor was opened from, shown again when it is left
+    before_editor: BoardState,
+    /// Scores and turns of a two-player game
+    versus: Option<Versus>,
+    /// A race against other players over the network
+    race: Option<Racer>,
+    /// A board shared with other players over the network
+    coop: Option<Partner>,
+    /// The shared board as last heard from the host
+    coop_state: Option<CoopState>,
     state: BoardState,
-    stopwatch: Stopwatch,
+}
+
+impl Default for SudokuBoard {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SudokuBoard {
     pub fn new() -> Self {
-        let ans_ = sudoku::Sudoku::generate_filled().to_bytes();
-        let sudoku_ =
-            sudoku::Sudoku::generate_unique_from(sudoku::Sudoku::from_bytes(ans_.clone()).unwrap())
-                .to_bytes();
-        let mut ans = [[0u8; 9]; 9];
-        for i in 0..9 {
-            for j in 0..9 {
-                ans[i][j] = ans_[i * 9 + j];
-            }
-        }
-        let mut sudoku = [[0u8; 9]; 9];
-        for i in 0..9 {
-            for j in 0..9 {
-                sudoku[i][j] = sudoku_[i * 9 + j];
-            }
-        }
-        let i = sudoku_.iter().position(|&x| x == 0).unwrap();
+        Self::with_game(Game::new())
+    }
+
+    /// Play a given puzzle. Returns `None` unless it has exactly one solution.
+    pub fn from_puzzle(puzzle: &crate::sudoku::Sudoku) -> Option<Self> {
+        Game::from_puzzle(puzzle).map(Self::with_game)
+    }
+
+    fn with_game(game: Game) -> Self {
         Self {
-            ans,
-            sudoku: sudoku.into(),
-            focus: [i / 9, i % 9],
-            moves: 0,
-            undos: 0,
-            hints: 0,
-            history: Vec::new(),
-            redo: Vec::new(),
-            conflict: None,
+            game,
+            note_mode: false,
+            color_mode: false,
+            highlighted: None,
+            pack: None,
+            pack_index: 0,
+            progress: Progress::new(),
+            symmetry: Symmetry::None,
+            editor: Editor::new(),
+            before_editor: BoardState::Config,
+            versus: None,
+            race: None,
+            coop: None,
+            coop_state: None,
             state: BoardState::Config,
-            stopwatch: Stopwatch::new(),
         }
     }
 
+    /// The game being played
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
     fn draw_config(&self, printer: &Printer) {
+        if let Some(pack) = &self.pack {
+            printer.print((2, 4), &pack.title(self.pack_index));
+        }
         printer.print((2, 6), "Press <Enter> to Start!");
+        if self.pack.is_none() {
+            printer.print((2, 8), &format!("Symmetry: < {} >", self.symmetry));
+        }
+        printer.print((2, 10), "Press <e> to enter a puzzle");
+        printer.print((2, 12), "Press <v> for two players");
     }
 
-    fn draw_finish(&self, printer: &Printer) {
-        printer.print((5, 2), "Congratulations!");
-        printer.print((5, 4), &format!("  Steps: {}", self.moves));
-        printer.print((5, 5), &format!("  Redos: {}", self.undos));
-        printer.print((5, 6), &format!("  Hints: {}", self.hints));
-        printer.print((1, 8), "Press <Enter> to continue");
+    fn draw_editor(&self, printer: &Printer) {
+        Self::draw_grid(printer);
+        let sudoku = self.editor.sudoku();
+        for i in 0..9 {
+            for j in 0..9 {
+                if sudoku[[i, j]] > 0 {
+                    printer.print(Self::coord_to_xy([i, j]), &format!("{}", sudoku[[i, j]]));
+                }
+            }
+        }
+        let conflicts = self.editor.conflicts();
+        printer.with_color(ColorStyle::highlight_inactive(), |p| {
+            p.with_effect(Effect::Reverse, |p| {
+                for &coord in &conflicts {
+                    p.print(Self::coord_to_xy(coord), &format!("{}", sudoku[coord]));
+                }
+            })
+        });
+        let focus = self.editor.focus();
+        let txt = match sudoku[focus] {
+            0 => " ".to_owned(),
+            v => format!("{}", v),
+        };
+        printer.with_color(ColorStyle::highlight(), |p| {
+            p.print(Self::coord_to_xy(focus), &txt);
+        });
+
+        let solutions = self.editor.solutions();
+        if solutions == Solutions::Unique {
+            printer.print((0, 14), "<Enter> play, <Esc> back");
+        } else {
+            printer.print((0, 14), "<Esc> back");
+        }
+
+        printer.print((16, 0), "Editor");
+        printer.print((17, 3), "Clues");
+        printer.print((18, 4), &format!("{}", self.editor.clues()));
+        printer.print((15, 6), "Solutions");
+        printer.print((16, 7), &format!("{}", solutions));
+        printer.print((15, 9), "Conflicts");
+        printer.print((18, 10), &format!("{}", conflicts.len()));
     }
 
-    fn draw_playing(&self, printer: &Printer) {
+    /// The borders of the board
+    fn draw_grid(printer: &Printer) {
         printer.print((0, 0), "┏━━━┯━━━┯━━━┓");
-        for (i, i_) in (1..4)
-            .into_iter()
-            .chain((5..8).into_iter())
-            .chain((9..12).into_iter())
-            .enumerate()
-        {
-            printer.print((0, i_), "┃");
-            printer.print((12, i_), "┃");
-            for (j, j_) in (1..4)
-                .into_iter()
-                .chain((5..8).into_iter())
-                .chain((9..12).into_iter())
-                .enumerate()
-            {
-                let n = self.sudoku[[i, j]];
-                if self.sudoku.available[i][j] {
-                    if n > 0 {
-                        printer.with_style(ColorStyle::secondary(), |p| {
-                            p.print((j_, i_), &format!("{}", n));
-                        });
-                    }
-                } else {
-                    // printer.with_effect(Effect::Bold, |p|{p.print((j_, i_), &format!("{}", n));})
-                    printer.print((j_, i_), &format!("{}", n));
-                }
-            }
+        for i in (1..4).chain(5..8).chain(9..12) {
+            printer.print((0, i), "┃");
+            printer.print((12, i), "┃");
         }
         for i in [4usize, 8, 12].iter() {
             printer.print((0, *i), "┠");
             printer.print((12, *i), "┨");
-            for j in (1..4)
-                .into_iter()
-                .chain((5..8).into_iter())
-                .chain((9..12).into_iter())
-            {
+            for j in (1..4).chain(5..8).chain(9..12) {
                 printer.print((j, *i), "─");
             }
         }
         for j in [4usize, 8].iter() {
-            for i in (1..4)
-                .into_iter()
-                .chain((5..8).into_iter())
-                .chain((9..12).into_iter())
-            {
+            for i in (1..4).chain(5..8).chain(9..12) {
                 printer.print((*j, i), "│");
             }
         }
@@ -138,15 +234,198 @@ impl SudokuBoard {
                 printer.print((*j, *i), "┼");
             }
         }
-        // for i in (1..18).step_by(2) {
-        //     printer.print((0, i), "┃");
-        //     printer.print((18, i), "┃");
-        // }
-        // printer.print((0, 0), "┏━━━━━━━━━━━━━━━━━━┓");
         printer.print((0, 12), "┗━━━┷━━━┷━━━┛");
+    }
+
+    fn draw_finish(&self, printer: &Printer) {
+        if let Some(versus) = &self.versus {
+            return Self::draw_result(versus, printer);
+        }
+        printer.print((5, 2), "Congratulations!");
+        printer.print((5, 4), &format!("  Steps: {}", self.game.moves()));
+        printer.print((5, 5), &format!("  Redos: {}", self.game.undos()));
+        printer.print((5, 6), &format!("  Hints: {}", self.game.hints()));
+        printer.print((5, 7), &format!(" Rating: {}", self.rating_text()));
+        if let Some(best) = self.progress.best(self.game.puzzle()) {
+            printer.print((5, 8), &format!("   Best: {}", best.hhmmss()));
+        }
+        if let Some(racer) = &self.race {
+            let place = racer
+                .standings()
+                .iter()
+                .filter_map(|s| s.finished_ms)
+                .filter(|&t| t < self.game.elapsed().as_millis() as u64)
+                .count()
+                + 1;
+            printer.print((5, 9), &format!("  Place: {}", place));
+            self.draw_race(racer, printer);
+        }
+        printer.print((1, 11), "Press <Enter> to continue");
+    }
+
+    /// The players of a cooperative game and the latest changes, beside the
+    /// board
+    fn draw_coop(&self, partner: &Partner, printer: &Printer) {
+        const WIDTH: usize = 18;
+        printer.print((28, 0), "Co-op");
+        let state = match &self.coop_state {
+            Some(state) => state,
+            None => return,
+        };
+        let color = |k: usize| ColorStyle::from(PLAYER_COLORS[k % PLAYER_COLORS.len()]);
+        for (k, member) in state.members.iter().enumerate().take(4) {
+            let note = if k == partner.id() {
+                " (you)"
+            } else if member.left {
+                " (left)"
+            } else {
+                ""
+            };
+            let name: String = member.name.chars().take(WIDTH - note.len()).collect();
+            printer.with_style(color(k), |p| {
+                p.print((28, 1 + k), &format!("{}{}", name, note))
+            });
+        }
+        printer.print((28, 6), "History");
+        let skip = state.history.len().saturating_sub(7);
+        for (y, entry) in state.history.iter().skip(skip).enumerate() {
+            let name = state.members[entry.player].name.chars().take(6);
+            let line: String = name
+                .chain(": ".chars())
+                .chain(entry.text.chars())
+                .take(WIDTH)
+                .collect();
+            printer.with_style(color(entry.player), |p| p.print((28, 7 + y), &line));
+        }
+        if !partner.is_connected() {
+            printer.print((28, 14), "Lost the host");
+        }
+    }
+
+    /// The progress of everyone in the race, beside the board
+    fn draw_race(&self, racer: &Racer, printer: &Printer) {
+        const BAR: usize = 10;
+        let cells = racer.cells().max(1);
+        printer.print((28, 0), &format!("Race (seed {})", racer.seed()));
+        for (k, standing) in racer.standings().iter().enumerate().take(6) {
+            let y = 1 + 2 * k;
+            let name: String = standing.name.chars().take(10).collect();
+            let state = if let Some(time) = standing.finish_time() {
+                format!("done {}", time.hhmmss())
+            } else if standing.left {
+                "left".to_owned()
+            } else {
+                format!("{} wrong", standing.mistakes)
+            };
+            let style = if k == racer.id() {
+                ColorStyle::title_primary()
+            } else {
+                ColorStyle::primary()
+            };
+            printer.with_style(style, |p| {
+                p.print((28, y), &format!("{:<10} {}", name, state))
+            });
+            let done = standing.filled.min(cells) * BAR / cells;
+            let bar = format!(
+                "{}{} {}/{}",
+                "█".repeat(done),
+                "░".repeat(BAR - done),
+                standing.filled,
+                cells
+            );
+            printer.print((28, y + 1), &bar);
+        }
+        if !racer.is_connected() {
+            printer.print((28, 14), "Lost the host");
+        }
+    }
+
+    /// The scores at the end of a two-player game
+    fn draw_result(versus: &Versus, printer: &Printer) {
+        let players = versus.players();
+        let headline = match versus.winner() {
+            Some(k) => format!("{} wins!", players[k].name),
+            None => "It's a draw!".to_owned(),
+        };
+        printer.print((5, 2), &headline);
+        for (k, player) in players.iter().enumerate() {
+            let y = 4 + 3 * k;
+            printer.with_style(ColorStyle::from(PLAYER_COLORS[k]), |p| {
+                p.print((5, y), &format!("{}: {} points", player.name, player.score))
+            });
+            printer.print(
+                (7, y + 1),
+                &format!("{} correct, {} wrong", player.correct, player.mistakes),
+            );
+        }
+        printer.print((1, 11), "Press <Enter> to continue");
+    }
+
+    fn draw_playing(&self, printer: &Printer) {
+        Self::draw_grid(printer);
+        let sudoku = self.game.sudoku();
+        for x in 0..81 {
+            let coord = [x / 9, x % 9];
+            if let Some((style, text)) = self.cell_text(coord) {
+                // the second player's digits are also underlined, so that
+                // telling them apart does not depend on colors
+                let effect = match self.owner(coord) {
+                    Some(1) => Effect::Underline,
+                    _ => Effect::Simple,
+                };
+                printer.with_style(style, |p| {
+                    p.with_effect(effect, |p| p.print(Self::coord_to_xy(coord), text))
+                });
+            }
+        }
+
+        // draw the colors the player marked cells with as their background
+        for x in 0..81 {
+            let coord = [x / 9, x % 9];
+            let color = match sudoku.cell(coord).color {
+                0 => continue,
+                c => Color::Light(CELL_COLORS[(c as usize - 1) % CELL_COLORS.len()]),
+            };
+            let text = self.cell_text(coord).map_or(" ", |(_, text)| text);
+            printer.with_color(ColorStyle::new(Color::Dark(BaseColor::Black), color), |p| {
+                p.print(Self::coord_to_xy(coord), text)
+            });
+        }
+
+        // draw the occurrences of the digit chosen in the tracker in bold
+        if let Some(v) = self.highlighted {
+            printer.with_color(ColorStyle::title_primary(), |p| {
+                p.with_effect(Effect::Bold, |p| {
+                    for x in (0..81).filter(|&x| sudoku[[x / 9, x % 9]] == v) {
+                        p.print(Self::coord_to_xy([x / 9, x % 9]), &v.to_string());
+                    }
+                })
+            });
+        }
+
+        // draw the selected cells
+        printer.with_color(ColorStyle::highlight_inactive(), |p| {
+            for &coord in self.game.selection() {
+                let text = self.cell_text(coord).map_or(" ", |(_, text)| text);
+                p.print(Self::coord_to_xy(coord), text);
+            }
+        });
+
+        // draw errors left on the board when conflicts are allowed, in
+        // reverse video and underlined until they are fixed
+        let errors = self.game.errors();
+        printer.with_color(ColorStyle::highlight_inactive(), |p| {
+            p.with_effect(Effect::Reverse, |p| {
+                p.with_effect(Effect::Underline, |p| {
+                    for &coord in &errors {
+                        p.print(Self::coord_to_xy(coord), &sudoku[coord].to_string());
+                    }
+                })
+            })
+        });
 
         // draw selected
-        let focus = self.sudoku[self.focus];
+        let focus = sudoku[self.game.focus()];
         let txt = if focus == 0 {
             " ".to_owned()
         } else {
@@ -156,26 +435,320 @@ impl SudokuBoard {
             printer.print(self.focus_xy(), &txt);
         });
 
-        // draw conflicted
-        if let Some(coord) = self.conflict {
+        // draw the cursors of the other players of a cooperative game, in
+        // their colors and underlined
+        if let (Some(partner), Some(state)) = (&self.coop, &self.coop_state) {
+            for (k, member) in state.members.iter().enumerate() {
+                if k == partner.id() || member.left {
+                    continue;
+                }
+                let text = match sudoku[member.cursor] {
+                    0 => " ".to_owned(),
+                    v => v.to_string(),
+                };
+                let color = PLAYER_COLORS[k % PLAYER_COLORS.len()];
+                printer.with_style(ColorStyle::from(color), |p| {
+                    p.with_effect(Effect::Reverse, |p| {
+                        p.with_effect(Effect::Underline, |p| {
+                            p.print(Self::coord_to_xy(member.cursor), &text)
+                        })
+                    })
+                });
+            }
+        }
+
+        // draw conflicted: the units where the rule is broken are underlined
+        // and marked on the border, and the clashing digits are shown in
+        // reverse video, so that conflicts do not depend on telling colors
+        // apart
+        if let Some(conflict) = self.game.conflict() {
+            for unit in conflict.units() {
+                for coord in unit.cells().filter(|&c| c != self.game.focus()) {
+                    let (style, text) = self
+                        .cell_text(coord)
+                        .unwrap_or((ColorStyle::primary(), " "));
+                    printer.with_style(style, |p| {
+                        p.with_effect(Effect::Underline, |p| {
+                            p.print(Self::coord_to_xy(coord), text)
+                        })
+                    });
+                }
+                match unit {
+                    Unit::Row(i) => {
+                        let (_, y) = Self::coord_to_xy([i, 0]);
+                        printer.print((0, y), ">");
+                        printer.print((12, y), "<");
+                    }
+                    Unit::Column(j) => {
+                        let (x, _) = Self::coord_to_xy([0, j]);
+                        printer.print((x, 0), "v");
+                        printer.print((x, 12), "^");
+                    }
+                    Unit::Box(_) => {}
+                }
+            }
             printer.with_color(ColorStyle::highlight_inactive(), |p| {
-                p.print(Self::coord_to_xy(coord), &format!("{}", self.sudoku[coord]));
+                p.with_effect(Effect::Reverse, |p| {
+                    for &coord in &conflict.with {
+                        p.print(Self::coord_to_xy(coord), &conflict.value.to_string());
+                    }
+                })
             });
         }
 
+        // draw pencil marks or candidates of the selected cell
+        // the color is also named, so that it does not depend on telling
+        // colors apart
+        let flag = if self.note_mode {
+            "[Note] "
+        } else if self.color_mode {
+            "[Color] "
+        } else {
+            ""
+        };
+        let mode = match self.game.cell(self.game.focus()).color {
+            0 => flag.trim_end().to_owned(),
+            c => format!("{}Color {}", flag, c),
+        };
+        if self.showing_candidates() {
+            let cands = Self::digits(self.game.candidates(self.game.focus()));
+            printer.print((0, 14), &format!("Cands: {} {}", cands, mode));
+        } else if self.settings().auto_candidates {
+            printer.print((0, 14), &format!("Cands: hidden {}", mode));
+        } else {
+            let notes = Self::digits(self.game.cell(self.game.focus()).notes);
+            printer.print((0, 14), &format!("Notes: {} {}", notes, mode));
+        }
+
+        // explain conflicts and mistakes in words
+        let mistake = self.versus.as_ref().and_then(|versus| {
+            versus.last_mistake().map(|m| {
+                format!(
+                    "{}: {} is wrong at row {}, column {}",
+                    versus.players()[m.player].name,
+                    m.value,
+                    m.coord[0] + 1,
+                    m.coord[1] + 1
+                )
+            })
+        });
+        let status = match self.game.conflict() {
+            Some(conflict) => conflict.to_string(),
+            None if errors.contains(&self.game.focus()) => {
+                let coord = self.game.focus();
+                let value = sudoku[coord];
+                let with = sudoku.conflicts(value, coord);
+                Conflict { coord, value, with }.to_string()
+            }
+            None if sudoku.is_full() && !errors.is_empty() => {
+                "The board is full but has conflicts".to_owned()
+            }
+            None if self.showing_candidates() => (0..81)
+                .map(|x| [x / 9, x % 9])
+                .find(|&c| {
+                    self.game.is_available(c) && sudoku[c] == 0 && self.game.candidates(c) == 0
+                })
+                .map(|[i, j]| format!("No candidates left in row {}, column {}", i + 1, j + 1))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let rejection = self.coop.as_ref().and_then(Partner::rejection);
+        printer.print((0, 15), &mistake.or(rejection).unwrap_or(status));
+        self.draw_palette(printer);
+        self.draw_tracker(printer);
+
         // draw info
         printer.print((14, 0), "Time Elapsed");
-        printer.print((16, 1), &self.stopwatch.read().hhmmss());
+        printer.print((16, 1), &self.game.elapsed().hhmmss());
+
+        if let Some(versus) = &self.versus {
+            for (k, player) in versus.players().iter().enumerate() {
+                let y = 3 + 3 * k;
+                let marker = if versus.turn() == k { "▶ " } else { "  " };
+                printer.with_style(ColorStyle::from(PLAYER_COLORS[k]), |p| {
+                    p.print((14, y), &format!("{}{}", marker, player.name))
+                });
+                printer.print((16, y + 1), &format!("{} points", player.score));
+            }
+        } else {
+            printer.print((18, 3), "Moves");
+            printer.print((20, 4), &format!("{}", self.game.moves()));
+
+            printer.print((18, 6), "Hint");
+            printer.print((18, 7), &format!("{}/Inf", self.game.hints()));
+        }
+
+        printer.print((17, 9), "Rating");
+        let rating = self.game.rating();
+        let rating = if rating.solved {
+            format!("{:.1}", rating.rating)
+        } else {
+            format!(">{:.1}", rating.rating)
+        };
+        printer.print((18, 10), &rating);
+
+        if let Some(racer) = &self.race {
+            self.draw_race(racer, printer);
+        }
+        if let Some(partner) = &self.coop {
+            self.draw_coop(partner, printer);
+        }
+
+        if let Some(versus) = &self.versus {
+            let name = &versus.players()[versus.turn()].name;
+            printer.print((14, 12), &format!("Turn: {}", name));
+            return;
+        }
+        let branches = self.branches().len();
+        let branch = if branches > 0 {
+            format!("{}/{}", self.active_branch() + 1, branches)
+        } else {
+            "-".to_owned()
+        };
+        printer.print((15, 12), &format!("Branch: {}", branch));
+        printer.print((15, 13), &format!("Marks: {}", self.bookmarks().len()));
+    }
+
+    /// Draw the buttons to play with the mouse: `1 2 3 4 5 6 7 8 9 Del Note
+    /// Color`, with `Note` or `Color` highlighted while entering pencil marks
+    /// or colors.
+    fn draw_palette(&self, printer: &Printer) {
+        printer.with_color(ColorStyle::highlight_inactive(), |p| {
+            for v in 1..=9 {
+                p.print((2 * (v - 1), PALETTE_ROW), &v.to_string());
+            }
+            p.print((18, PALETTE_ROW), "Del");
+        });
+        let style = |on: bool| {
+            if on {
+                ColorStyle::highlight()
+            } else {
+                ColorStyle::highlight_inactive()
+            }
+        };
+        printer.with_color(style(self.note_mode), |p| {
+            p.print((22, PALETTE_ROW), "Note")
+        });
+        printer.with_color(style(self.color_mode), |p| {
+            p.print((27, PALETTE_ROW), "Color")
+        });
+    }
+
+    /// How many of each digit, from 1 to 9, are still missing from the board.
+    /// Conflicting entries are not counted (givens always are), so that a digit
+    /// is missing none only once it is on the board nine times without
+    /// breaking the rules.
+    pub fn missing(&self) -> [usize; 9] {
+        let sudoku = self.game.sudoku();
+        let errors = sudoku.errors();
+        let mut missing = [0; 9];
+        for v in 1..=9 {
+            let conflicting = errors
+                .iter()
+                .filter(|&&coord| sudoku[coord] == v && self.game.puzzle()[coord] == 0)
+                .count();
+            missing[v as usize - 1] = 9usize.saturating_sub(sudoku.count(v) - conflicting);
+        }
+        missing
+    }
+
+    /// Draw how many of each digit are still missing under the palette, e.g.
+    /// `3 0 5 1 2 4 0 6 2 left`. Digits missing none are greyed out in the
+    /// palette, and the highlighted digit is shown as selected.
+    fn draw_tracker(&self, printer: &Printer) {
+        let grey = ColorStyle::new(Color::Light(BaseColor::Black), PaletteColor::View);
+        for (k, left) in self.missing().iter().copied().enumerate() {
+            let (v, x) = (k as u8 + 1, 2 * k);
+            let style = if self.highlighted == Some(v) {
+                ColorStyle::highlight()
+            } else if left == 0 {
+                grey
+            } else {
+                ColorStyle::primary()
+            };
+            printer.with_color(style, |p| p.print((x, TRACKER_ROW), &left.to_string()));
+            if left == 0 {
+                printer.with_color(grey, |p| p.print((x, PALETTE_ROW), &v.to_string()));
+            }
+        }
+        printer.print((18, TRACKER_ROW), "left");
+    }
+
+    /// Highlight every `v` on the board, or stop if it is highlighted already.
+    fn toggle_highlight(&mut self, v: u8) {
+        self.highlighted = match self.highlighted {
+            Some(w) if w == v => None,
+            _ => Some(v),
+        };
+    }
+
+    /// The digit highlighted on the board, chosen in the digit tracker
+    pub fn highlighted(&self) -> Option<u8> {
+        self.highlighted
+    }
+
+    /// How a cell is drawn while playing, if it is not blank
+    fn cell_text(&self, coord: Coord) -> Option<(ColorStyle, &'static str)> {
+        const DIGITS: [&str; 10] = ["", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        let [i, j] = coord;
+        let sudoku = self.game.sudoku();
+        let n = sudoku[coord];
+        if !sudoku.available[i][j] {
+            Some((ColorStyle::primary(), DIGITS[n as usize]))
+        } else if n > 0 {
+            let style = match self.owner(coord) {
+                Some(k) => ColorStyle::from(PLAYER_COLORS[k]),
+                None => ColorStyle::secondary(),
+            };
+            Some((style, DIGITS[n as usize]))
+        } else if self.showing_candidates() {
+            // no candidates left means a mistake has been made
+            Some((ColorStyle::highlight_inactive(), "!"))
+                .filter(|_| self.game.candidates(coord) == 0)
+        } else if !self.settings().auto_candidates && sudoku.notes[i][j] != 0 {
+            Some((ColorStyle::tertiary(), "·"))
+        } else {
+            None
+        }
+    }
+
+    /// SE rating of the puzzle and the hardest technique it needs
+    pub fn rating(&self) -> Rating {
+        self.game.rating()
+    }
+
+    fn rating_text(&self) -> String {
+        let rating = self.game.rating();
+        match (rating.solved, rating.hardest) {
+            (true, Some(t)) => format!("{:.1} ({})", rating.rating, t),
+            _ => format!("> {:.1}", rating.rating),
+        }
+    }
+
+    /// Space-separated digits of a bitmask of candidates or notes
+    fn digits(mask: u16) -> String {
+        (1..=9)
+            .filter(|v| mask & (1 << v) != 0)
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 
-        printer.print((18, 3), "Moves");
-        printer.print((20, 4), &format!("{}", self.moves));
+    pub fn settings(&self) -> Settings {
+        self.game.settings()
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.game.set_settings(settings);
+    }
 
-        printer.print((18, 6), "Hint");
-        printer.print((18, 7), &format!("{}/Inf", self.hints));
+    fn showing_candidates(&self) -> bool {
+        let settings = self.game.settings();
+        settings.auto_candidates && settings.show_candidates
     }
 
     fn focus_xy(&self) -> (usize, usize) {
-        Self::coord_to_xy(self.focus)
+        Self::coord_to_xy(self.game.focus())
     }
 
     fn coord_to_xy(coord: [usize; 2]) -> (usize, usize) {
@@ -183,6 +756,16 @@ impl SudokuBoard {
         (C[coord[1]], C[coord[0]])
     }
 
+    /// The cell under the mouse at `position`
+    fn cell_at(offset: Vec2, position: Vec2) -> Option<Coord> {
+        let xy = position.checked_sub(offset)?;
+        if xy.x < 13 && xy.y < 13 {
+            Self::xy_to_coord((xy.y, xy.x))
+        } else {
+            None
+        }
+    }
+
     fn xy_to_coord(xy: (usize, usize)) -> Option<[usize; 2]> {
         const C: [usize; 13] = [
             usize::MAX,
@@ -208,136 +791,534 @@ impl SudokuBoard {
         }
     }
 
-    fn set_sodoku_value_and_check_finish(&mut self, coord: [usize; 2], v: u8) {
-        self.sudoku[coord] = v;
-        if self.sudoku.finished() {
-            self.state = BoardState::Finish;
-            self.stopwatch.pause();
+    /// Pass an action on to the game, and show the finish screen once the
+    /// puzzle is solved.
+    fn apply(&mut self, action: Action) {
+        // the game hidden behind the editor stays as it is
+        if self.is_editing() {
+            return;
+        }
+        if self.coop.is_some() {
+            return self.share(action);
+        }
+        // in a two-player game, digits are only entered by `place`, and
+        // hints and undoing would be unfair; hints are not allowed in races
+        // either
+        let allowed = match action {
+            Action::ToggleNote(..)
+            | Action::ToggleNotes(_)
+            | Action::ClearNotes
+            | Action::Color(_)
+            | Action::MoveFocus(_)
+            | Action::SetFocus(_)
+            | Action::ExtendSelection(_)
+            | Action::Select(_)
+            | Action::SelectUnit(_) => true,
+            Action::Hint => self.versus.is_none() && self.race.is_none(),
+            _ => self.versus.is_none(),
+        };
+        if !allowed {
+            return;
+        }
+        if let (Some(racer), Action::Fill(coord, v)) = (&mut self.race, action) {
+            let [i, j] = coord;
+            let sudoku = self.game.sudoku();
+            if self.game.is_available(coord)
+                && sudoku[coord] != v
+                && self.game.solution()[i][j] != v
+            {
+                racer.mistake();
+            }
+        }
+        let event = self.game.apply(action);
+        if let Some(racer) = &mut self.race {
+            // losing the host shows in the race panel, not as an error here
+            let _ = racer.update(&self.game);
+            if event == GameEvent::Completed {
+                let _ = racer.finish(self.game.elapsed());
+            }
+        }
+        if event == GameEvent::Completed {
+            self.finish();
         }
     }
 
-    fn fill(&mut self, v: u8) {
-        self.redo.clear();
-        self.moves += 1;
-        match self.sudoku.conflict(v, self.focus) {
-            None => {
-                self.conflict = None;
-                self.history.push((self.focus, self.sudoku[self.focus]));
-                self.set_sodoku_value_and_check_finish(self.focus, v);
+    /// Ask the host of a cooperative game to make a change; the board changes
+    /// when the host says so. Only moving the cursor happens here.
+    fn share(&mut self, action: Action) {
+        let partner = match &mut self.coop {
+            Some(partner) => partner,
+            None => return,
+        };
+        let revision = self.coop_state.as_ref().map_or(0, |state| state.revision);
+        let edit = match action {
+            Action::Fill(coord, value) => Edit::Fill { coord, value },
+            Action::Clear(coord) => Edit::Clear { coord },
+            Action::ToggleNote(coord, value) => Edit::Note { coord, value },
+            Action::Undo => Edit::Undo,
+            Action::Redo => Edit::Redo,
+            // the host only knows single cells, so every mark changed is
+            // sent on its own
+            Action::ToggleNotes(_) | Action::ClearNotes => {
+                let value = match action {
+                    Action::ToggleNotes(v) => Some(v),
+                    _ => None,
+                };
+                let cmd = self.game.notes_command(value);
+                for change in &cmd.changes {
+                    // with automatic candidates, it is the eliminations
+                    // that change
+                    let (before, after) = (change.before, change.after);
+                    let marks =
+                        (before.notes ^ after.notes) | (before.eliminated ^ after.eliminated);
+                    for value in (1..=9).filter(|v| marks & (1 << v) != 0) {
+                        let coord = change.coord;
+                        let _ = partner.edit(Edit::Note { coord, value }, revision);
+                    }
+                }
+                return;
+            }
+            // colors are the player's own
+            Action::Color(_) => {
+                self.game.apply(action);
+                return;
+            }
+            Action::MoveFocus(_)
+            | Action::SetFocus(_)
+            | Action::ExtendSelection(_)
+            | Action::Select(_)
+            | Action::SelectUnit(_) => {
+                self.game.apply(action);
+                let _ = partner.move_cursor(self.game.focus());
+                return;
             }
-            Some(coord) => {
-                self.conflict = Some(coord);
+            // hints and bookmarks are not shared
+            _ => return,
+        };
+        // losing the host shows in the panel, not as an error here
+        let _ = partner.edit(edit, revision);
+    }
+
+    /// Enter `n` into the focused cell as an answer, or erase the selected
+    /// cells if it is 0. In note mode, toggle the pencil mark `n` of all
+    /// selected cells, or clear their marks if it is 0, as one step.
+    fn enter(&mut self, n: u8) {
+        if self.color_mode {
+            // digits beyond the colors are ignored by the game
+            return self.apply(Action::Color(n));
+        }
+        if self.note_mode {
+            return match n {
+                0 => self.apply(Action::ClearNotes),
+                n => self.apply(Action::ToggleNotes(n)),
+            };
+        }
+        // one answer at a time, so that it is one turn in a two-player game
+        // and at most one mistake in a race
+        let focus = self.game.focus();
+        match n {
+            0 => {
+                for coord in self.game.selection().to_vec() {
+                    self.apply(Action::Clear(coord));
+                }
             }
+            n if self.versus.is_some() => self.place(focus, n),
+            n => self.apply(Action::Fill(focus, n)),
         }
     }
 
-    pub fn hint(&mut self) {
-        let mut avail = Vec::new();
-        for i in 0..9 {
-            for j in 0..9 {
-                if self.sudoku.available[i][j] {
-                    avail.push([i, j]);
+    /// Switch between entering answers and pencil marks.
+    fn toggle_note_mode(&mut self) {
+        self.note_mode = !self.note_mode;
+        self.color_mode = false;
+    }
+
+    /// Switch between entering answers and colors, for keyboards without Alt.
+    fn toggle_color_mode(&mut self) {
+        self.color_mode = !self.color_mode;
+        self.note_mode = false;
+    }
+
+    /// Move the pencil mark of a cell on to the next digit: none, 1, 2, ...,
+    /// 9 and none again. Other marks of the cell are kept.
+    fn cycle_note(&mut self, coord: Coord) {
+        let notes = self.game.cell(coord).notes;
+        let highest = (1..=9).rev().find(|&v| notes & 1 << v != 0);
+        if let Some(v) = highest {
+            self.apply(Action::ToggleNote(coord, v));
+        }
+        let next = highest.unwrap_or(0) + 1;
+        if next <= 9 {
+            self.apply(Action::ToggleNote(coord, next));
+        }
+    }
+
+    /// Play with the mouse: click a cell to select it, drag over cells to
+    /// select several, right-click to cycle a pencil mark, and click the
+    /// palette to enter digits or the tracker below it to highlight them.
+    fn on_mouse(&mut self, offset: Vec2, position: Vec2, event: MouseEvent) -> EventResult {
+        match (event, Self::cell_at(offset, position)) {
+            (MouseEvent::WheelDown, _) => self.apply(Action::MoveFocus(Direction::Next)),
+            (MouseEvent::WheelUp, _) => self.apply(Action::MoveFocus(Direction::Prev)),
+            (MouseEvent::Press(MouseButton::Right), Some(coord)) => {
+                self.apply(Action::SetFocus(coord));
+                self.cycle_note(coord);
+            }
+            (MouseEvent::Press(_), Some(coord)) => self.apply(Action::SetFocus(coord)),
+            (MouseEvent::Hold(MouseButton::Left), Some(coord)) => self.apply(Action::Select(coord)),
+            (MouseEvent::Press(MouseButton::Left), None) => {
+                let xy = match position.checked_sub(offset) {
+                    Some(xy) => xy,
+                    None => return EventResult::Ignored,
+                };
+                match (xy.y, PaletteKey::at(xy.x)) {
+                    (PALETTE_ROW, Some(PaletteKey::Digit(v))) => self.enter(v),
+                    (PALETTE_ROW, Some(PaletteKey::Erase)) => self.enter(0),
+                    (PALETTE_ROW, Some(PaletteKey::Note)) => self.toggle_note_mode(),
+                    (PALETTE_ROW, Some(PaletteKey::Color)) => self.toggle_color_mode(),
+                    (TRACKER_ROW, Some(PaletteKey::Digit(v))) => self.toggle_highlight(v),
+                    _ => return EventResult::Ignored,
                 }
             }
+            _ => return EventResult::Ignored,
         }
+        EventResult::Consumed(None)
+    }
 
-        if avail.len() > 0 {
-            self.hints += 1;
-            let coord = avail[rand::random::<usize>() % avail.len()];
-            let [i, j] = coord;
-            self.set_sodoku_value_and_check_finish(coord, self.ans[i][j]);
-            self.sudoku.available[i][j] = false;
+    /// Enter a digit for the player whose turn it is.
+    fn place(&mut self, coord: Coord, v: u8) {
+        if let Some(versus) = &mut self.versus {
+            if versus.place(&mut self.game, coord, v) == Placement::Completed {
+                self.finish();
+            }
         }
     }
 
+    /// The player who placed the digit at `coord` in a two-player game
+    fn owner(&self, coord: Coord) -> Option<usize> {
+        self.versus.as_ref().and_then(|versus| versus.owner(coord))
+    }
+
+    pub fn versus(&self) -> Option<&Versus> {
+        self.versus.as_ref()
+    }
+
+    pub fn hint(&mut self) {
+        self.apply(Action::Hint);
+    }
+
     pub fn undo(&mut self) {
-        self.undos += 1;
-        self.moves += 1;
-        if let Some((coord, v)) = self.history.pop() {
-            self.redo.push((self.focus, self.sudoku[self.focus]));
-            self.sudoku[coord] = v;
-        }
+        self.apply(Action::Undo);
     }
 
     pub fn redo(&mut self) {
-        self.moves += 1;
-        if let Some((coord, v)) = self.redo.pop() {
-            self.history.push((coord, v));
-            self.sudoku[coord] = v;
+        self.apply(Action::Redo);
+    }
+
+    /// Set a checkpoint at the current position, e.g. before making a guess.
+    pub fn bookmark(&mut self) {
+        self.apply(Action::Bookmark);
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        self.game.history().bookmarks()
+    }
+
+    /// Go back (or forward) to the `k`th bookmark.
+    pub fn jump_to_bookmark(&mut self, k: usize) {
+        self.apply(Action::JumpToBookmark(k));
+    }
+
+    /// Moves that can be redone from the current position, one per branch.
+    pub fn branches(&self) -> Vec<Command> {
+        self.game.history().branches()
+    }
+
+    pub fn active_branch(&self) -> usize {
+        self.game.history().active_branch()
+    }
+
+    /// Choose which branch the next redo follows.
+    pub fn select_branch(&mut self, k: usize) {
+        self.apply(Action::SelectBranch(k));
+    }
+
+    fn cycle_branch(&mut self, forward: bool) {
+        let n = self.branches().len();
+        if n > 1 {
+            let k = self.active_branch();
+            let k = if forward {
+                (k + 1) % n
+            } else {
+                (k + n - 1) % n
+            };
+            self.select_branch(k);
         }
     }
 
-    pub fn restart(&mut self) {
-        *self = SudokuBoard::new();
+    fn finish(&mut self) {
+        self.state = BoardState::Finish;
+        // failing to save progress should not interrupt the game; the time
+        // of a two-player or cooperative game, or of a solve helped by hints
+        // or undoing, is not a personal best
+        let clean = self.game.hints() == 0 && self.game.undos() == 0;
+        if self.versus.is_none() && self.coop.is_none() && clean {
+            let _ = self
+                .progress
+                .record(self.game.puzzle(), self.game.elapsed());
+        }
+        if let Some(pack) = &self.pack {
+            // continue with the next puzzle not completed yet
+            let n = pack.entries.len();
+            self.pack_index = (1..=n)
+                .map(|k| (self.pack_index + k) % n)
+                .find(|&k| !self.progress.completed(&pack.entries[k].puzzle))
+                .unwrap_or((self.pack_index + 1) % n);
+        }
     }
 
-    fn move_focus_right(&mut self) {
-        let [i, j] = self.focus;
-        for k in 1..9 {
-            let j_ = (j + k) % 9;
-            if self.sudoku.available[i][j_] {
-                self.focus = [i, j_];
-                return;
+    /// Start a new game: the current puzzle of the pack if one is loaded, or
+    /// else a random puzzle. Puzzles of the pack without a unique solution
+    /// are skipped, and a pack with none to play is closed.
+    pub fn restart(&mut self) {
+        let playable = self.pack.as_ref().and_then(|pack| {
+            let n = pack.entries.len();
+            (0..n)
+                .map(|k| (self.pack_index + k) % n)
+                .find_map(|k| Game::from_puzzle(&pack.entries[k].puzzle).map(|game| (k, game)))
+        });
+        let mut game = match playable {
+            Some((k, game)) => {
+                self.pack_index = k;
+                game
             }
+            None => {
+                self.pack = None;
+                Game::generate(&mut Generator::new().symmetry(self.symmetry))
+                    .expect("a classic puzzle")
+            }
+        };
+        game.set_settings(self.game.settings());
+        self.set_game(game);
+        self.state = BoardState::Config;
+    }
+
+    fn start(&mut self) {
+        self.restart();
+        self.state = BoardState::Playing;
+        self.game.start();
+    }
+
+    /// Race on the puzzle of the host `racer` has joined.
+    pub fn start_race(&mut self, racer: Racer) {
+        if let Some(mut game) = Game::from_puzzle(racer.puzzle()) {
+            game.set_settings(self.game.settings());
+            game.start();
+            self.set_game(game);
+            self.race = Some(racer);
+            self.state = BoardState::Playing;
         }
     }
-    fn move_focus_left(&mut self) {
-        let [i, j] = self.focus;
-        for k in 1..9 {
-            let j_ = (9 + j - k) % 9;
-            if self.sudoku.available[i][j_] {
-                self.focus = [i, j_];
-                return;
-            }
+
+    /// Solve the puzzle of the host `partner` has joined together with the
+    /// other players.
+    pub fn start_coop(&mut self, mut partner: Partner) {
+        if let Some(mut game) = Game::from_puzzle(partner.puzzle()) {
+            game.set_settings(self.game.settings());
+            game.start();
+            let _ = partner.move_cursor(game.focus());
+            self.set_game(game);
+            self.coop = Some(partner);
+            self.state = BoardState::Playing;
         }
     }
-    fn move_focus_down(&mut self) {
-        let [i, j] = self.focus;
-        for k in 1..9 {
-            let i_ = (i + k) % 9;
-            if self.sudoku.available[i_][j] {
-                self.focus = [i_, j];
-                return;
+
+    /// Follow the board of a cooperative game, with the latest changes heard
+    /// from the host.
+    pub fn sync(&mut self) {
+        if let BoardState::Playing = self.state {
+            if let Some(state) = self.coop.as_mut().and_then(Partner::update) {
+                let event = self.game.sync(state.sudoku.clone());
+                self.coop_state = Some(state);
+                if event == GameEvent::Completed {
+                    self.finish();
+                }
             }
         }
     }
-    fn move_focus_up(&mut self) {
-        let [i, j] = self.focus;
-        for k in 1..9 {
-            let i_ = (9 + i - k) % 9;
-            if self.sudoku.available[i_][j] {
-                self.focus = [i_, j];
-                return;
-            }
+
+    /// Play `game` from now on, on its own: leaving any multiplayer game and
+    /// forgetting the highlighted digit.
+    fn set_game(&mut self, game: Game) {
+        self.game = game;
+        self.highlighted = None;
+        self.end_multiplayer();
+    }
+
+    /// Leave any two-player, race or cooperative game.
+    fn end_multiplayer(&mut self) {
+        self.versus = None;
+        self.race = None;
+        self.coop = None;
+        self.coop_state = None;
+    }
+
+    /// Start a new game for two players taking turns.
+    pub fn start_versus(&mut self) {
+        self.start();
+        self.versus = Some(Versus::new());
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Generate random puzzles with this pattern of clues from now on.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    fn cycle_symmetry(&mut self, forward: bool) {
+        let all = &Symmetry::ALL;
+        let k = all.iter().position(|&s| s == self.symmetry).unwrap_or(0);
+        let k = if forward {
+            (k + 1) % all.len()
+        } else {
+            (k + all.len() - 1) % all.len()
+        };
+        self.set_symmetry(all[k]);
+    }
+
+    /// Type in a puzzle to play. The game is paused meanwhile, and continues
+    /// if the editor is left with <Esc>.
+    pub fn edit(&mut self) {
+        if self.is_editing() {
+            return;
         }
+        self.pause();
+        self.before_editor = self.state;
+        self.state = BoardState::Editor;
+    }
+
+    /// Leave the editor without playing its puzzle.
+    fn close_editor(&mut self) {
+        self.state = self.before_editor;
+        self.resume();
     }
 
-    fn move_focus_next(&mut self) {
-        let [mut i, mut j] = self.focus;
-        let mut x = 9 * i + j;
-        for _ in 1..81 {
-            x = (x + 1) % 81;
+    /// Whether the editor is shown instead of the game
+    pub fn is_editing(&self) -> bool {
+        matches!(self.state, BoardState::Editor)
+    }
 
-            i = x / 9;
-            j = x % 9;
-            if self.sudoku.available[i][j] {
-                self.focus = [i, j];
-                return;
+    /// Play the puzzle typed in the editor, if it has a unique solution.
+    fn play_edited(&mut self) {
+        if let Ok(mut game) = self.editor.play() {
+            game.set_settings(self.game.settings());
+            game.start();
+            self.set_game(game);
+            self.editor = Editor::new();
+            self.state = BoardState::Playing;
+        }
+    }
+
+    /// The game being played, to be saved.
+    pub fn save_game(&self) -> SavedGame {
+        let name = self.pack.as_ref().map(|pack| pack.title(self.pack_index));
+        self.game.to_saved(name)
+    }
+
+    /// The puzzle being played, or every puzzle of the pack, to be printed.
+    pub fn sheet(&self, whole_pack: bool) -> Sheet {
+        let current =
+            Puzzle::from_sudoku(self.game.puzzle()).expect("games have a unique solution");
+        match &self.pack {
+            Some(pack) if whole_pack => {
+                let items = pack.entries.iter().enumerate().filter_map(|(k, entry)| {
+                    Puzzle::from_sudoku(&entry.puzzle).map(|p| Item::new(pack.title(k), p))
+                });
+                Sheet::new(pack.name.clone()).items(items)
             }
+            Some(pack) => {
+                Sheet::new(pack.name.clone()).item(Item::new(pack.title(self.pack_index), current))
+            }
+            None => Sheet::new("Sudoku").item(Item::new("Sudoku", current)),
         }
     }
 
-    fn move_focus_prev(&mut self) {
-        let [mut i, mut j] = self.focus;
-        let mut x = 9 * i + j;
-        for _ in 1..81 {
-            x = (81 + x - 1) % 81;
+    /// Continue playing a saved game.
+    pub fn load_game(&mut self, saved: SavedGame) -> Result<(), SaveError> {
+        self.resume_game(Game::from_saved(saved)?);
+        Ok(())
+    }
+
+    /// Continue playing a game from where it was left.
+    pub fn resume_game(&mut self, game: Game) {
+        self.set_game(game);
+        self.game.resume();
+        self.state = BoardState::Playing;
+    }
 
-            i = x / 9;
-            j = x % 9;
-            if self.sudoku.available[i][j] {
-                self.focus = [i, j];
-                return;
+    /// Stop the timer while the board is hidden.
+    pub fn pause(&mut self) {
+        if let BoardState::Playing = self.state {
+            self.game.pause();
+        }
+    }
+
+    /// Restart the timer when the board is shown again.
+    pub fn resume(&mut self) {
+        if let BoardState::Playing = self.state {
+            self.game.resume();
+        }
+    }
+
+    /// A short name for the board, e.g. on its tab
+    pub fn title(&self) -> String {
+        if let Some(racer) = &self.race {
+            return format!("Race {}", racer.seed());
+        }
+        if self.coop.is_some() {
+            return "Co-op".to_owned();
+        }
+        match (&self.pack, &self.state) {
+            (Some(pack), _) => pack.title(self.pack_index),
+            (None, BoardState::Config) => "New game".to_owned(),
+            (None, BoardState::Editor) => "Editor".to_owned(),
+            (None, _) => format!("Sudoku {:.1}", self.game.rating().rating),
+        }
+    }
+
+    /// Use saved progress, e.g. `Progress::load()`.
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Work through a pack, starting from its first uncompleted puzzle.
+    pub fn load_pack(&mut self, pack: Pack) {
+        self.pack_index = pack
+            .entries
+            .iter()
+            .position(|e| !self.progress.completed(&e.puzzle))
+            .unwrap_or(0);
+        self.pack = Some(pack);
+        self.restart();
+    }
+
+    pub fn pack(&self) -> Option<&Pack> {
+        self.pack.as_ref()
+    }
+
+    /// Start playing the `k`th puzzle of the pack.
+    pub fn play_pack_puzzle(&mut self, k: usize) {
+        if let Some(pack) = &self.pack {
+            if k < pack.entries.len() {
+                self.pack_index = k;
+                self.start();
             }
         }
     }
@@ -347,25 +1328,63 @@ impl View for SudokuBoard {
     fn draw(&self, printer: &Printer) {
         match self.state {
             BoardState::Config => self.draw_config(printer),
+            BoardState::Editor => self.draw_editor(printer),
             BoardState::Playing => self.draw_playing(printer),
             BoardState::Finish => self.draw_finish(printer),
         }
     }
     fn required_size(&mut self, _: Vec2) -> Vec2 {
         //  Vec2::new(19, 19)
-        Vec2::new(16, 13)
+        if self.race.is_some() || self.coop.is_some() {
+            // room for the race or co-op panel
+            Vec2::new(46, 18)
+        } else {
+            // the palette is wider than the board
+            Vec2::new(32, 18)
+        }
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
         match self.state {
             BoardState::Config => {
                 match event {
-                    Event::Key(Key::Enter) => {
-                        self.restart();
-                        self.state = BoardState::Playing;
-                        self.stopwatch = Stopwatch::new();
-                        self.stopwatch.resume();
+                    Event::Key(Key::Enter) => self.start(),
+                    Event::Key(Key::Left) if self.pack.is_none() => self.cycle_symmetry(false),
+                    Event::Key(Key::Right) if self.pack.is_none() => self.cycle_symmetry(true),
+                    Event::Char('e') => self.edit(),
+                    Event::Char('v') => self.start_versus(),
+                    _ => return EventResult::Ignored,
+                }
+                EventResult::Consumed(None)
+            }
+            BoardState::Editor => {
+                match event {
+                    Event::Char(c) if c.is_ascii_digit() => {
+                        let v = c.to_digit(10).unwrap() as u8;
+                        self.editor.set(self.editor.focus(), v);
+                    }
+                    Event::Key(Key::Backspace) | Event::Key(Key::Del) => {
+                        self.editor.set(self.editor.focus(), 0)
+                    }
+                    Event::Key(Key::Right) => self.editor.move_focus(Direction::Right),
+                    Event::Key(Key::Left) => self.editor.move_focus(Direction::Left),
+                    Event::Key(Key::Down) => self.editor.move_focus(Direction::Down),
+                    Event::Key(Key::Up) => self.editor.move_focus(Direction::Up),
+                    Event::Key(Key::Tab) => self.editor.move_focus(Direction::Next),
+                    Event::Shift(Key::Tab) => self.editor.move_focus(Direction::Prev),
+                    Event::Mouse {
+                        offset,
+                        position,
+                        event: MouseEvent::Press(_),
+                    } if position > offset && position - offset < cursive::XY::new(12, 12) => {
+                        if let Some(coord) =
+                            Self::xy_to_coord((position.y - offset.y, position.x - offset.x))
+                        {
+                            self.editor.set_focus(coord);
+                        }
                     }
+                    Event::Key(Key::Enter) => self.play_edited(),
+                    Event::Key(Key::Esc) => self.close_editor(),
                     _ => return EventResult::Ignored,
                 }
                 EventResult::Consumed(None)
@@ -374,51 +1393,60 @@ impl View for SudokuBoard {
                 match event {
                     Event::Char(c) => {
                         if c.is_numeric() {
-                            let n = c.to_digit(10).unwrap() as u8;
-                            if n > 0 {
-                                self.fill(n);
-                            }
+                            self.enter(c.to_digit(10).unwrap() as u8);
                         } else {
                             match c {
                                 'h' => self.hint(),
+                                'm' => self.bookmark(),
+                                'n' => self.toggle_note_mode(),
+                                'k' => self.toggle_color_mode(),
+                                'a' => {
+                                    let mut settings = self.settings();
+                                    settings.auto_candidates = !settings.auto_candidates;
+                                    self.set_settings(settings);
+                                }
+                                'c' => {
+                                    let mut settings = self.settings();
+                                    settings.show_candidates = !settings.show_candidates;
+                                    self.set_settings(settings);
+                                }
+                                ']' => self.cycle_branch(true),
+                                '[' => self.cycle_branch(false),
+                                'R' | 'C' | 'B' => {
+                                    let [row, column, square] = Unit::of(self.game.focus());
+                                    let unit = match c {
+                                        'R' => row,
+                                        'C' => column,
+                                        _ => square,
+                                    };
+                                    self.apply(Action::SelectUnit(unit));
+                                }
                                 _ => return EventResult::Ignored,
                             }
                         }
                         return EventResult::Consumed(None);
                     }
-                    Event::Key(Key::Right) => {
-                        self.move_focus_right();
+                    Event::Key(Key::Backspace) | Event::Key(Key::Del) => self.enter(0),
+                    Event::AltChar(c) if c.is_ascii_digit() => {
+                        self.apply(Action::Color(c.to_digit(10).unwrap() as u8))
+                    }
+                    Event::Key(Key::Right) => self.apply(Action::MoveFocus(Direction::Right)),
+                    Event::Key(Key::Left) => self.apply(Action::MoveFocus(Direction::Left)),
+                    Event::Key(Key::Down) => self.apply(Action::MoveFocus(Direction::Down)),
+                    Event::Key(Key::Up) => self.apply(Action::MoveFocus(Direction::Up)),
+                    Event::Key(Key::Tab) => self.apply(Action::MoveFocus(Direction::Next)),
+                    Event::Shift(Key::Tab) => self.apply(Action::MoveFocus(Direction::Prev)),
+                    Event::Shift(Key::Right) => {
+                        self.apply(Action::ExtendSelection(Direction::Right))
                     }
-                    Event::Key(Key::Left) => self.move_focus_left(),
-                    Event::Key(Key::Down) => self.move_focus_down(),
-                    Event::Key(Key::Up) => self.move_focus_up(),
-                    Event::Key(Key::Tab) => self.move_focus_next(),
-                    Event::Shift(Key::Tab) => self.move_focus_prev(),
+                    Event::Shift(Key::Left) => self.apply(Action::ExtendSelection(Direction::Left)),
+                    Event::Shift(Key::Down) => self.apply(Action::ExtendSelection(Direction::Down)),
+                    Event::Shift(Key::Up) => self.apply(Action::ExtendSelection(Direction::Up)),
                     Event::Mouse {
                         offset,
                         position,
                         event,
-                    } => {
-                        match event {
-                            MouseEvent::WheelDown => self.move_focus_next(),
-                            MouseEvent::WheelUp => self.move_focus_prev(),
-                            MouseEvent::Press(_)
-                                if position > offset
-                                    && position - offset < cursive::XY::new(12, 12) =>
-                            {
-                                if let Some(coord) = Self::xy_to_coord((
-                                    position.y - offset.y,
-                                    position.x - offset.x,
-                                )) {
-                                    if self.sudoku.available[coord[0]][coord[1]] {
-                                        self.focus = coord;
-                                    }
-                                }
-                            }
-                            _ => return EventResult::Ignored,
-                        }
-                        return EventResult::Consumed(None);
-                    }
+                    } => return self.on_mouse(offset, position, event),
                     Event::CtrlChar('z') => self.undo(),
                     // Event::CtrlChar('Z') => self.redo(), // doesn't work
                     // Event::CtrlShift(Key::???) => self.redo(), // Key::Char?
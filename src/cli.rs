@@ -0,0 +1,699 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Command line subcommands. Running `sudoku` without one starts the game.
+
+use crate::coop::{self, Partner};
+use crate::engine::Game;
+use crate::game;
+use crate::generator::{self, Difficulty, Generator, Puzzle, Shape, Symmetry, Variant};
+use crate::linear;
+use crate::net::DEFAULT_PORT;
+use crate::pack::Pack;
+use crate::print::{Format, Item, Paper, Sheet};
+use crate::race::{Host, Racer};
+use crate::save::SavedGame;
+use crate::solver::{rate, Solver};
+use crate::sudoku::Sudoku;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const USAGE: &str = "\
+Usage:
+    sudoku [-s SYMMETRY]        Play sudoku. SYMMETRY is the pattern of the clues:
+                                none, rotational, horizontal, vertical,
+                                diagonal or dihedral
+    sudoku --accessible [FILE]  Play by typing commands, with every change
+                                announced as a line of text, for screen
+                                readers. FILE is a saved game to continue.
+    sudoku rate [-v] [PUZZLE]...
+                                Rate puzzles given as 81 characters (0 or . for
+                                empty cells), or read one per line from stdin.
+                                With -v, print every step of the solution.
+    sudoku pack FILE            Play the puzzles in FILE in order, one per line
+    sudoku generate [OPTIONS]   Generate puzzles, one per line with their rating
+                                (a valid pack), or as JSON with their solutions
+        -n COUNT                Number of puzzles (default 1)
+        -d DIFFICULTY           easy, medium, hard or expert (classic 9x9 only)
+        -s SYMMETRY             Pattern of the clues, as above
+        --variant VARIANT       classic or diagonal
+        --size SIZE             Rows of the board: 4, 6, 8 or 9 (default)
+        --seed SEED             Generate the same puzzles every time
+        -j THREADS              Threads to use (default: one per core)
+        --json                  Write JSON
+        -o FILE                 Write to FILE instead of standard output
+        --format FORMAT         Write a printable sheet instead: pdf or ps
+                                (implied by -o FILE.pdf or FILE.ps), with the
+                                print options below
+    sudoku print FILE [OPTIONS] Write the puzzles of a pack as a printable sheet
+        -o FILE                 Write to FILE instead of standard output
+        --format FORMAT         pdf (default) or ps, or from the extension of -o
+        --per-page N            Puzzles on each page, 1 to 6 (default 4)
+        --solutions             Add pages with the solutions
+        --title TITLE           Title at the top of each page
+        --paper PAPER           a4 (default) or letter
+    sudoku load FILE            Continue a saved game, or play the (first)
+                                puzzle written by `sudoku generate --json`
+    sudoku host [OPTIONS]       Host a race on one puzzle, which others join
+                                with `sudoku join`
+        --port PORT             Port to listen on (default 7878)
+        --seed SEED             Race on the puzzle generated from SEED
+        --name NAME             Your name in the standings
+        --coop                  Solve one shared board together instead
+    sudoku join HOST [--name NAME] [--coop]
+                                Join the game hosted at HOST, e.g. 192.168.1.2
+                                or example.org:7878; --coop if it is shared
+    sudoku help                 Print this message";
+
+/// Run the subcommand in `args` (excluding the program name), returning the
+/// exit code, or `None` if there is no subcommand.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (cmd, args) = args.split_first()?;
+    let code = match cmd.as_str() {
+        "rate" => rate_cmd(args),
+        "pack" => pack_cmd(args),
+        "generate" => generate_cmd(args),
+        "print" => print_cmd(args),
+        "load" => load_cmd(args),
+        "host" => host_cmd(args),
+        "join" => join_cmd(args),
+        "-s" | "--symmetry" => play_cmd(args),
+        "-a" | "--accessible" => accessible_cmd(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("Unknown command '{}'\n\n{}", cmd, USAGE);
+            2
+        }
+    };
+    Some(code)
+}
+
+/// Puzzles from the arguments, or from stdin if there are none.
+fn read_puzzles(args: &[String]) -> Vec<String> {
+    if args.is_empty() {
+        io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .filter(|l| !l.trim().is_empty())
+            .collect()
+    } else {
+        args.to_vec()
+    }
+}
+
+fn rate_cmd(args: &[String]) -> i32 {
+    let verbose = args.iter().any(|a| a == "-v");
+    let args: Vec<String> = args.iter().filter(|a| *a != "-v").cloned().collect();
+    let mut code = 0;
+    for puzzle in read_puzzles(&args) {
+        let sudoku: Sudoku = match puzzle.parse() {
+            Ok(sudoku) => sudoku,
+            Err(e) => {
+                eprintln!("{}: {}", puzzle.trim(), e);
+                code = 1;
+                continue;
+            }
+        };
+        println!("{} {}", sudoku.to_line(), rate(&sudoku));
+        if verbose {
+            for step in Solver::new(&sudoku).solve() {
+                match step.placement {
+                    Some(([i, j], v)) => {
+                        println!("    {}: r{}c{} = {}", step.technique, i + 1, j + 1, v)
+                    }
+                    None => println!(
+                        "    {}: {} candidate(s) removed",
+                        step.technique, step.eliminations
+                    ),
+                }
+            }
+        }
+    }
+    code
+}
+
+fn play_cmd(args: &[String]) -> i32 {
+    let symmetry: Symmetry = match args {
+        [name] => match name.parse() {
+            Ok(symmetry) => symmetry,
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                return 2;
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    game::run_symmetric(symmetry);
+    0
+}
+
+fn accessible_cmd(args: &[String]) -> i32 {
+    let game = match args {
+        [] => {
+            let mut game = Game::new();
+            game.start();
+            game
+        }
+        [path] => match SavedGame::load(path).and_then(Game::from_saved) {
+            Ok(mut game) => {
+                game.resume();
+                game
+            }
+            Err(e) => {
+                eprintln!("Cannot load {}: {}", path, e);
+                return 1;
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match linear::run(game, io::stdin().lock(), io::stdout().lock()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn load_cmd(args: &[String]) -> i32 {
+    let path = match args {
+        [path] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match SavedGame::load(path).and_then(game::run_saved) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Cannot load {}: {}", path, e);
+            1
+        }
+    }
+}
+
+/// Options of `sudoku host` and `sudoku join`
+struct NetOptions {
+    port: u16,
+    seed: u64,
+    name: String,
+    /// Share one board instead of racing
+    coop: bool,
+    /// The host to join
+    address: Option<String>,
+}
+
+impl NetOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self {
+            port: DEFAULT_PORT,
+            seed: rand::random(),
+            name: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "Player".to_owned()),
+            coop: false,
+            address: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') && options.address.is_none() {
+                options.address = Some(arg.clone());
+                continue;
+            }
+            if arg == "--coop" {
+                options.coop = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            let invalid = |e: &dyn std::fmt::Display| format!("{}: {}", arg, e);
+            match arg.as_str() {
+                "--port" => options.port = value.parse().map_err(|e| invalid(&e))?,
+                "--seed" => options.seed = value.parse().map_err(|e| invalid(&e))?,
+                "--name" => options.name = value.clone(),
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Join a race, or a cooperative game, and play it.
+fn play_online(address: &str, name: &str, coop: bool) -> io::Result<()> {
+    if coop {
+        game::run_coop(Partner::join(address, name)?);
+    } else {
+        game::run_race(Racer::join(address, name)?);
+    }
+    Ok(())
+}
+
+fn host_cmd(args: &[String]) -> i32 {
+    let options = match NetOptions::parse(args) {
+        Ok(options) if options.address.is_none() => options,
+        Ok(_) => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let addr = ("0.0.0.0", options.port);
+    let bound = if options.coop {
+        coop::Host::bind(addr, options.seed).map(|host| host.local_addr())
+    } else {
+        Host::bind(addr, options.seed).map(|host| host.local_addr())
+    };
+    let port = match bound {
+        Ok(addr) => addr.port(),
+        Err(e) => {
+            eprintln!("Cannot listen on port {}: {}", options.port, e);
+            return 1;
+        }
+    };
+    match play_online(&format!("127.0.0.1:{}", port), &options.name, options.coop) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Cannot join the game: {}", e);
+            1
+        }
+    }
+}
+
+fn join_cmd(args: &[String]) -> i32 {
+    let (options, address) = match NetOptions::parse(args) {
+        Ok(mut options) => match options.address.take() {
+            Some(address) => (options, address),
+            None => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        },
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    // the default port unless one is given
+    let address = if address.contains(':') {
+        address
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+    match play_online(&address, &options.name, options.coop) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Cannot join {}: {}", address, e);
+            1
+        }
+    }
+}
+
+fn pack_cmd(args: &[String]) -> i32 {
+    let path = match args {
+        [path] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match Pack::load(path) {
+        Ok(pack) => {
+            game::run_pack(pack);
+            0
+        }
+        Err(e) => {
+            eprintln!("Cannot open {}: {}", path, e);
+            1
+        }
+    }
+}
+
+/// Options of printable sheets
+struct PrintOptions {
+    format: Option<Format>,
+    per_page: usize,
+    solutions: bool,
+    title: Option<String>,
+    paper: Paper,
+}
+
+impl PrintOptions {
+    fn new() -> Self {
+        Self {
+            format: None,
+            per_page: 4,
+            solutions: false,
+            title: None,
+            paper: Paper::A4,
+        }
+    }
+
+    /// Take `arg`, and its value from `args`, if it is a print option.
+    fn parse(&mut self, arg: &str, args: &mut std::slice::Iter<String>) -> Result<bool, String> {
+        if arg == "--solutions" {
+            self.solutions = true;
+            return Ok(true);
+        }
+        if !matches!(arg, "--format" | "--per-page" | "--title" | "--paper") {
+            return Ok(false);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = |e: &dyn std::fmt::Display| format!("{}: {}", arg, e);
+        match arg {
+            "--format" => self.format = Some(value.parse().map_err(|e| invalid(&e))?),
+            "--per-page" => match value.parse() {
+                Ok(n @ 1..=6) => self.per_page = n,
+                _ => return Err(invalid(&"expected a number from 1 to 6")),
+            },
+            "--title" => self.title = Some(value.clone()),
+            _ => self.paper = value.parse().map_err(|e| invalid(&e))?,
+        }
+        Ok(true)
+    }
+
+    fn sheet(&self, default_title: &str) -> Sheet {
+        Sheet::new(self.title.as_deref().unwrap_or(default_title))
+            .per_page(self.per_page)
+            .solutions(self.solutions)
+            .paper(self.paper)
+    }
+}
+
+/// Standard output, or `path` if there is one.
+fn create_output(path: Option<&str>) -> Result<Box<dyn Write>, i32> {
+    match path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Ok(Box::new(io::BufWriter::new(file))),
+            Err(e) => {
+                eprintln!("Cannot create {}: {}", path, e);
+                Err(1)
+            }
+        },
+        None => Ok(Box::new(io::stdout().lock())),
+    }
+}
+
+fn print_cmd(args: &[String]) -> i32 {
+    let (path, options) = match parse_print_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let pack = match Pack::load(&path) {
+        Ok(pack) => pack,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}", path, e);
+            return 1;
+        }
+    };
+    let mut items = Vec::new();
+    for (k, entry) in pack.entries.iter().enumerate() {
+        match Puzzle::from_sudoku(&entry.puzzle) {
+            Some(puzzle) => items.push(Item::new(pack.title(k), puzzle)),
+            None => eprintln!("Skipping {}: no unique solution", pack.title(k)),
+        }
+    }
+    let sheet = options.print.sheet(&pack.name).items(items);
+    let mut out = match create_output(options.output.as_deref()) {
+        Ok(out) => out,
+        Err(code) => return code,
+    };
+    match out
+        .write_all(&sheet.render(options.format()))
+        .and_then(|_| out.flush())
+    {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+struct PrintArgs {
+    print: PrintOptions,
+    output: Option<String>,
+}
+
+impl PrintArgs {
+    /// The format asked for, or implied by the output file
+    fn format(&self) -> Format {
+        self.print
+            .format
+            .or_else(|| self.output.as_ref().and_then(Format::from_path))
+            .unwrap_or_default()
+    }
+}
+
+fn parse_print_args(args: &[String]) -> Result<(String, PrintArgs), String> {
+    let mut path = None;
+    let mut options = PrintArgs {
+        print: PrintOptions::new(),
+        output: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if options.print.parse(arg, &mut args)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-o" => {
+                let value = args.next().ok_or("missing value for -o")?;
+                options.output = Some(value.clone());
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    let path = path.ok_or("missing pack file")?;
+    Ok((path, options))
+}
+
+struct GenerateOptions {
+    count: usize,
+    difficulty: Option<Difficulty>,
+    symmetry: Symmetry,
+    variant: Variant,
+    shape: Shape,
+    seed: u64,
+    threads: usize,
+    json: bool,
+    output: Option<String>,
+    print: PrintOptions,
+}
+
+impl GenerateOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self {
+            count: 1,
+            difficulty: None,
+            symmetry: Symmetry::None,
+            variant: Variant::Classic,
+            shape: Shape::CLASSIC,
+            seed: rand::random(),
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            json: false,
+            output: None,
+            print: PrintOptions::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if options.print.parse(arg, &mut args)? {
+                continue;
+            }
+            if arg == "--json" {
+                options.json = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            let invalid = |e: &dyn std::fmt::Display| format!("{}: {}", arg, e);
+            match arg.as_str() {
+                "-n" => options.count = value.parse().map_err(|e| invalid(&e))?,
+                "-d" => options.difficulty = Some(value.parse().map_err(|e| invalid(&e))?),
+                "-s" => options.symmetry = value.parse().map_err(|e| invalid(&e))?,
+                "--variant" => options.variant = value.parse().map_err(|e| invalid(&e))?,
+                "--size" => options.shape = value.parse().map_err(|e| invalid(&e))?,
+                "--seed" => options.seed = value.parse().map_err(|e| invalid(&e))?,
+                "-j" => options.threads = value.parse().map_err(|e| invalid(&e))?,
+                "-o" => options.output = Some(value.clone()),
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+        if options.print.format.is_none() {
+            options.print.format = options.output.as_ref().and_then(Format::from_path);
+        }
+        Ok(options)
+    }
+
+    fn generator(&self) -> Generator {
+        let generator = Generator::new()
+            .shape(self.shape)
+            .variant(self.variant)
+            .symmetry(self.symmetry);
+        match self.difficulty {
+            Some(difficulty) => generator.difficulty(difficulty),
+            None => generator,
+        }
+    }
+}
+
+/// A generated puzzle as written by `sudoku generate --json`
+#[derive(Serialize)]
+struct GeneratedPuzzle {
+    puzzle: String,
+    solution: String,
+    size: usize,
+    variant: Variant,
+    symmetry: Symmetry,
+    clues: usize,
+    /// Only classic 9x9 puzzles are rated
+    rating: Option<f32>,
+    hardest: Option<&'static str>,
+}
+
+impl GeneratedPuzzle {
+    fn new(puzzle: &Puzzle) -> Self {
+        let rating = puzzle.sudoku().map(|sudoku| rate(&sudoku));
+        Self {
+            puzzle: puzzle.to_line(),
+            solution: puzzle.solution.iter().map(|v| v.to_string()).collect(),
+            size: puzzle.shape.size(),
+            variant: puzzle.variant,
+            symmetry: puzzle.symmetry,
+            clues: puzzle.clues(),
+            rating: rating.filter(|r| r.solved).map(|r| r.rating),
+            hardest: rating.and_then(|r| r.hardest).map(|t| t.name()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GeneratedBatch {
+    seed: u64,
+    difficulty: Option<Difficulty>,
+    puzzles: Vec<GeneratedPuzzle>,
+}
+
+fn generate_cmd(args: &[String]) -> i32 {
+    let options = match GenerateOptions::parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let mut out = match create_output(options.output.as_deref()) {
+        Ok(out) => out,
+        Err(code) => return code,
+    };
+    let show_progress = io::stderr().is_terminal();
+    let puzzles = generator::batch(
+        &options.generator(),
+        options.seed,
+        options.count,
+        options.threads,
+        |done| {
+            if show_progress {
+                eprint!("\rGenerated {}/{}", done, options.count);
+            }
+        },
+    );
+    if show_progress {
+        eprintln!();
+    }
+    let result = if let Some(format) = options.print.format {
+        write_sheet(&mut out, &options, puzzles, format)
+    } else if options.json {
+        let batch = GeneratedBatch {
+            seed: options.seed,
+            difficulty: options.difficulty,
+            puzzles: puzzles.iter().map(GeneratedPuzzle::new).collect(),
+        };
+        serde_json::to_writer_pretty(&mut out, &batch)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(out))
+    } else {
+        write_lines(&mut out, &options, &puzzles)
+    };
+    match result.and_then(|_| out.flush()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Write puzzles as a printable sheet, with the options as its subtitle.
+fn write_sheet(
+    out: &mut dyn Write,
+    options: &GenerateOptions,
+    puzzles: Vec<Puzzle>,
+    format: Format,
+) -> io::Result<()> {
+    let mut subtitle = format!(
+        "{} {} puzzles, symmetry {}, seed {}",
+        options.variant, options.shape, options.symmetry, options.seed
+    );
+    if let Some(difficulty) = options.difficulty {
+        subtitle += &format!(", difficulty {}", difficulty);
+    }
+    let items = puzzles
+        .into_iter()
+        .enumerate()
+        .map(|(k, puzzle)| Item::new(format!("Puzzle {}", k + 1), puzzle));
+    let sheet = options
+        .print
+        .sheet("Sudoku")
+        .subtitle(subtitle)
+        .items(items);
+    out.write_all(&sheet.render(format))
+}
+
+/// Write puzzles in the format of packs, with the options as a comment.
+fn write_lines(
+    out: &mut dyn Write,
+    options: &GenerateOptions,
+    puzzles: &[Puzzle],
+) -> io::Result<()> {
+    write!(
+        out,
+        "# {} {} puzzles, symmetry {}, seed {}",
+        options.variant, options.shape, options.symmetry, options.seed
+    )?;
+    match options.difficulty {
+        Some(difficulty) => writeln!(out, ", difficulty {}", difficulty)?,
+        None => writeln!(out)?,
+    }
+    for puzzle in puzzles {
+        match puzzle.sudoku() {
+            Some(sudoku) => writeln!(out, "{} {}", puzzle.to_line(), rate(&sudoku))?,
+            None => writeln!(out, "{}", puzzle.to_line())?,
+        }
+    }
+    Ok(())
+}
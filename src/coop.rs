@@ -0,0 +1,482 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Solving one puzzle together over TCP.
+//!
+//! A [`Host`] keeps the only real board. Players join it as [`Partner`]s and
+//! send it [`Edit`]s; it applies them one at a time and sends everyone the
+//! new [`State`]: the board, where each player's cursor is, and the shared
+//! history saying who did what.
+//!
+//! Every state has a revision number, and edits say which revision the
+//! player was looking at. When two players change the same cell at the same
+//! time, the first edit to reach the host wins and the other is rejected,
+//! since its author had not seen the cell change. Undo and redo apply to the
+//! shared history, so they are rejected whenever the board changed since the
+//! player last saw it.
+//!
+//! ```no_run
+//! use sudoku_tui::coop::{Edit, Host, Partner};
+//!
+//! let host = Host::bind("0.0.0.0:7878", 42)?;
+//! let mut partner = Partner::join("127.0.0.1:7878", "Alice")?;
+//! partner.edit(Edit::Fill { coord: [0, 2], value: 4 }, 0)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::engine::{Action, Event, Game};
+use crate::generator::Generator;
+use crate::history::Command;
+use crate::net::{lock, outbox, receive, send};
+use crate::sudoku::{Coord, Sudoku};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A change a player asks the host to make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "edit", rename_all = "snake_case")]
+pub enum Edit {
+    Fill {
+        coord: Coord,
+        value: u8,
+    },
+    Clear {
+        coord: Coord,
+    },
+    /// Toggle a pencil mark
+    Note {
+        coord: Coord,
+        value: u8,
+    },
+    /// Undo the latest change, whoever made it
+    Undo,
+    Redo,
+}
+
+impl Edit {
+    /// The cell changed, except for undo and redo
+    fn coord(self) -> Option<Coord> {
+        match self {
+            Self::Fill { coord, .. } | Self::Clear { coord } | Self::Note { coord, .. } => {
+                Some(coord)
+            }
+            Self::Undo | Self::Redo => None,
+        }
+    }
+
+    /// Whether the cell and digit exist, so that the edit can be tried
+    fn is_valid(self) -> bool {
+        let value = match self {
+            Self::Fill { value, .. } | Self::Note { value, .. } => value,
+            _ => 0,
+        };
+        self.coord().into_iter().all(|[i, j]| i < 9 && j < 9) && value <= 9
+    }
+
+    fn action(self) -> Action {
+        match self {
+            Self::Fill { coord, value } => Action::Fill(coord, value),
+            Self::Clear { coord } => Action::Clear(coord),
+            Self::Note { coord, value } => Action::ToggleNote(coord, value),
+            Self::Undo => Action::Undo,
+            Self::Redo => Action::Redo,
+        }
+    }
+}
+
+/// A player of a cooperative game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Member {
+    pub name: String,
+    pub cursor: Coord,
+    /// Whether the player has disconnected
+    pub left: bool,
+}
+
+/// A line of the shared history, e.g. `r1c3: 4` or `undid r1c3: 4`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The player who made the change
+    pub player: usize,
+    pub text: String,
+}
+
+/// Everything the host shares with the players
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    /// Increases with every change to the board
+    pub revision: u64,
+    pub sudoku: Sudoku,
+    pub members: Vec<Member>,
+    /// Every change, oldest first
+    pub history: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Join { name: String },
+    Edit { edit: Edit, revision: u64 },
+    Cursor { coord: Coord },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HostMessage {
+    Welcome {
+        id: usize,
+        puzzle: String,
+    },
+    State(Box<State>),
+    /// An edit was not made
+    Rejected {
+        reason: String,
+    },
+}
+
+/// The board as the host keeps it
+struct Room {
+    game: Game,
+    revision: u64,
+    /// The revision at which each cell last changed, and by whom
+    changed: [[(u64, usize); 9]; 9],
+    members: Vec<Member>,
+    writers: Vec<Option<Sender<HostMessage>>>,
+    history: Vec<Entry>,
+    /// Changes that can be undone, latest last, with their author
+    done: Vec<(usize, String)>,
+    /// Changes that can be redone, latest undone last
+    undone: Vec<(usize, String)>,
+}
+
+impl Room {
+    fn state(&self) -> State {
+        State {
+            revision: self.revision,
+            sudoku: self.game.sudoku().clone(),
+            members: self.members.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Queue the state for every player; the lock on the room is not held
+    /// while it is written.
+    fn broadcast(&mut self) {
+        let message = HostMessage::State(Box::new(self.state()));
+        for writer in &mut self.writers {
+            if let Some(sender) = writer {
+                if sender.send(message.clone()).is_err() {
+                    *writer = None;
+                }
+            }
+        }
+    }
+
+    /// Make `edit` for `player`, who last saw `revision`, or say why not.
+    fn edit(&mut self, player: usize, edit: Edit, revision: u64) -> Result<(), String> {
+        match edit.coord() {
+            Some([i, j]) => {
+                let (at, by) = self.changed[i][j];
+                if at > revision && by != player {
+                    return Err(format!(
+                        "{} changed row {}, column {} first",
+                        self.members[by].name,
+                        i + 1,
+                        j + 1
+                    ));
+                }
+            }
+            None if revision < self.revision => {
+                return Err("The board changed in the meantime; try again".to_owned());
+            }
+            None => {}
+        }
+
+        let before = self.game.sudoku().clone();
+        let text = match edit {
+            Edit::Fill { coord, value } => Command::fill(&before, coord, value).to_string(),
+            Edit::Clear { coord } => Command::clear(&before, coord).to_string(),
+            Edit::Note { coord, value } => Command::toggle_note(&before, coord, value).to_string(),
+            Edit::Undo => match self.done.last() {
+                Some((author, text)) => self.attribute("undid", *author, text),
+                None => return Err("Nothing to undo".to_owned()),
+            },
+            Edit::Redo => match self.undone.last() {
+                Some((author, text)) => self.attribute("redid", *author, text),
+                None => return Err("Nothing to redo".to_owned()),
+            },
+        };
+        match self.game.apply(edit.action()) {
+            Event::Ignored => return Ok(()),
+            Event::Conflict(_) => {
+                let conflict = self.game.conflict().expect("the digit was rejected");
+                return Err(conflict.to_string());
+            }
+            Event::Changed | Event::Completed => {}
+        }
+        match edit {
+            Edit::Undo => {
+                let move_ = self.done.pop().expect("there was a change to undo");
+                self.undone.push(move_);
+            }
+            Edit::Redo => {
+                let move_ = self.undone.pop().expect("there was a change to redo");
+                self.done.push(move_);
+            }
+            _ => {
+                self.done.push((player, text.clone()));
+                self.undone.clear();
+            }
+        }
+        self.revision += 1;
+        let after = self.game.sudoku();
+        for i in 0..9 {
+            for j in 0..9 {
+                if before[[i, j]] != after[[i, j]]
+                    || before.notes[i][j] != after.notes[i][j]
+                    || before.eliminated[i][j] != after.eliminated[i][j]
+                {
+                    self.changed[i][j] = (self.revision, player);
+                }
+            }
+        }
+        self.history.push(Entry { player, text });
+        Ok(())
+    }
+
+    /// `undid r1c3: 4 (Alice)`
+    fn attribute(&self, verb: &str, author: usize, text: &str) -> String {
+        format!("{} {} ({})", verb, text, self.members[author].name)
+    }
+}
+
+/// Serves a cooperative game in the background until the program exits.
+#[derive(Debug)]
+pub struct Host {
+    addr: SocketAddr,
+    seed: u64,
+}
+
+impl Host {
+    /// Listen on `addr` (port 0 picks a free one) and share the puzzle
+    /// generated from `seed`.
+    pub fn bind(addr: impl ToSocketAddrs, seed: u64) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let puzzle = Generator::new().seed(seed).generate();
+        let sudoku = puzzle.sudoku().expect("a classic puzzle");
+        let mut game = Game::from_puzzle(&sudoku).expect("generated puzzles have one solution");
+        // the clock stops when the puzzle is solved, so it has to be running
+        game.start();
+        let room = Arc::new(Mutex::new(Room {
+            game,
+            revision: 0,
+            changed: [[(0, 0); 9]; 9],
+            members: Vec::new(),
+            writers: Vec::new(),
+            history: Vec::new(),
+            done: Vec::new(),
+            undone: Vec::new(),
+        }));
+        let puzzle = puzzle.to_line();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (room, puzzle) = (Arc::clone(&room), puzzle.clone());
+                thread::spawn(move || serve(stream, room, puzzle));
+            }
+        });
+        Ok(Self { addr, seed })
+    }
+
+    /// The address players join, e.g. with the port picked by the system
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// Talk to one player until they hang up.
+fn serve(stream: TcpStream, room: Arc<Mutex<Room>>, puzzle: String) {
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+    let name = match receive(&mut reader) {
+        Ok(Some(ClientMessage::Join { name })) => name,
+        _ => return,
+    };
+    let writer = outbox(stream);
+    let id = {
+        let mut room = lock(&room);
+        let id = room.members.len();
+        if writer.send(HostMessage::Welcome { id, puzzle }).is_err() {
+            return;
+        }
+        let cursor = room.game.focus();
+        room.members.push(Member {
+            name,
+            cursor,
+            left: false,
+        });
+        room.writers.push(Some(writer));
+        room.broadcast();
+        id
+    };
+    while let Ok(Some(message)) = receive(&mut reader) {
+        // cells off the board are ignored before they can do any harm
+        match message {
+            ClientMessage::Edit { edit, .. } if !edit.is_valid() => continue,
+            ClientMessage::Cursor { coord: [i, j] } if i >= 9 || j >= 9 => continue,
+            _ => {}
+        }
+        let mut room = lock(&room);
+        match message {
+            ClientMessage::Edit { edit, revision } => {
+                if let Err(reason) = room.edit(id, edit, revision) {
+                    if let Some(writer) = &room.writers[id] {
+                        let _ = writer.send(HostMessage::Rejected { reason });
+                    }
+                    continue;
+                }
+            }
+            ClientMessage::Cursor { coord } => room.members[id].cursor = coord,
+            ClientMessage::Join { .. } => continue,
+        }
+        room.broadcast();
+    }
+    let mut room = lock(&room);
+    room.members[id].left = true;
+    room.writers[id] = None;
+    room.broadcast();
+}
+
+/// What a partner has heard from the host
+#[derive(Debug, Default)]
+struct Inbox {
+    state: Option<State>,
+    /// Whether `state` changed since it was last taken
+    fresh: bool,
+    rejection: Option<String>,
+}
+
+/// A player of a cooperative game.
+#[derive(Debug)]
+pub struct Partner {
+    id: usize,
+    puzzle: Sudoku,
+    writer: TcpStream,
+    inbox: Arc<Mutex<Inbox>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl Partner {
+    /// Join the game hosted at `addr` under `name`.
+    pub fn join(addr: impl ToSocketAddrs, name: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let name = name.to_owned();
+        send(&mut writer, &ClientMessage::Join { name })?;
+        let (id, puzzle) = match receive(&mut reader)? {
+            Some(HostMessage::Welcome { id, puzzle }) => (id, puzzle),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the host did not send a puzzle",
+                ))
+            }
+        };
+        let puzzle = puzzle
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let connected = Arc::new(AtomicBool::new(true));
+        let (shared, flag) = (Arc::clone(&inbox), Arc::clone(&connected));
+        thread::spawn(move || {
+            while let Ok(Some(message)) = receive(&mut reader) {
+                let mut inbox = lock(&shared);
+                match message {
+                    HostMessage::State(state) => {
+                        inbox.state = Some(*state);
+                        inbox.fresh = true;
+                    }
+                    HostMessage::Rejected { reason } => inbox.rejection = Some(reason),
+                    HostMessage::Welcome { .. } => {}
+                }
+            }
+            flag.store(false, Ordering::Relaxed);
+        });
+        Ok(Self {
+            id,
+            puzzle,
+            writer,
+            inbox,
+            connected,
+        })
+    }
+
+    /// The position of the player in [`State::members`]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The puzzle as it was given
+    pub fn puzzle(&self) -> &Sudoku {
+        &self.puzzle
+    }
+
+    /// Ask the host to make `edit`, made while looking at `revision`.
+    pub fn edit(&mut self, edit: Edit, revision: u64) -> io::Result<()> {
+        lock(&self.inbox).rejection = None;
+        send(&mut self.writer, &ClientMessage::Edit { edit, revision })
+    }
+
+    /// Show the others where the player is.
+    pub fn move_cursor(&mut self, coord: Coord) -> io::Result<()> {
+        send(&mut self.writer, &ClientMessage::Cursor { coord })
+    }
+
+    /// The latest state heard from the host
+    pub fn state(&self) -> Option<State> {
+        lock(&self.inbox).state.clone()
+    }
+
+    /// The latest state, if it changed since the last call
+    pub fn update(&mut self) -> Option<State> {
+        let mut inbox = lock(&self.inbox);
+        if !inbox.fresh {
+            return None;
+        }
+        inbox.fresh = false;
+        inbox.state.clone()
+    }
+
+    /// Why the latest edit was not made, if it was not
+    pub fn rejection(&self) -> Option<String> {
+        lock(&self.inbox).rejection.clone()
+    }
+
+    /// Whether the host can still be heard from
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Partner {
+    /// Leave the game; see [`Racer`](crate::race::Racer) for why this is
+    /// needed.
+    fn drop(&mut self) {
+        let _ = self.writer.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,867 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! The game engine, independent of any user interface.
+//!
+//! A [`Game`] holds the puzzle being played along with its history, timer and
+//! statistics. Frontends feed it [`Action`]s, react to the [`Event`] each one
+//! returns, and render what the query methods report. The terminal interface
+//! in [`board`](crate::board) is one such frontend.
+//!
+//! ```
+//! use sudoku_tui::engine::{Action, Event, Game};
+//!
+//! let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+//! let mut game = Game::from_puzzle(&puzzle.parse().unwrap()).unwrap();
+//! game.start();
+//! assert_eq!(game.apply(Action::Fill([0, 2], 4)), Event::Changed);
+//! assert_eq!(game.apply(Action::Fill([0, 3], 5)), Event::Conflict([0, 0]));
+//! assert_eq!(game.apply(Action::Undo), Event::Changed);
+//! assert_eq!(game.cell([0, 2]).value, 0);
+//! ```
+
+use crate::generator::{self, Generator, Solutions, Symmetry, Variant};
+use crate::history::{CellChange, Command, CommandKind, UndoTree};
+use crate::save::{Metadata, SaveError, SavedGame, VERSION};
+use crate::solver::{self, Rating};
+use crate::sudoku::{Cell, Coord, Sudoku, SudokuMatrix, Unit, COLORS};
+use clock_core::stopwatch::Stopwatch;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Options affecting how the game assists the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Compute the candidates of every empty cell from the board
+    pub auto_candidates: bool,
+    /// Display the automatic candidates
+    pub show_candidates: bool,
+    /// Candidates removed by the player stay removed even when the candidates
+    /// of the cell are recomputed
+    pub keep_eliminations: bool,
+    /// Digits breaking the rules are entered anyway and reported by
+    /// [`Game::errors`] until fixed
+    pub allow_conflicts: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_candidates: false,
+            show_candidates: true,
+            keep_eliminations: false,
+            allow_conflicts: false,
+        }
+    }
+}
+
+/// Directions in which the focus can move. The focus skips given cells and
+/// wraps around the edges of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// The next cell in reading order
+    Next,
+    /// The previous cell in reading order
+    Prev,
+}
+
+impl Direction {
+    /// The cell `k` steps away from `coord`, wrapping around the edges
+    fn step(self, coord: Coord, k: usize) -> Coord {
+        let [i, j] = coord;
+        let x = 9 * i + j;
+        match self {
+            Self::Right => [i, (j + k) % 9],
+            Self::Left => [i, (9 + j - k % 9) % 9],
+            Self::Down => [(i + k) % 9, j],
+            Self::Up => [(9 + i - k % 9) % 9, j],
+            Self::Next => [(x + k) % 81 / 9, (x + k) % 9],
+            Self::Prev => [(81 + x - k % 81) % 81 / 9, (81 + x - k % 81) % 9],
+        }
+    }
+}
+
+/// A digit that was not entered because the same digit is already in one of
+/// the units of the cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Where the digit was to be entered
+    pub coord: Coord,
+    pub value: u8,
+    /// Every cell already holding the digit in the row, column or box, in
+    /// reading order
+    pub with: Vec<Coord>,
+}
+
+impl Conflict {
+    /// The units of the cell where the rule would be broken
+    pub fn units(&self) -> Vec<Unit> {
+        Unit::of(self.coord)
+            .iter()
+            .copied()
+            .filter(|unit| self.with.iter().any(|&c| unit.contains(c)))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Conflict {
+    /// `5 conflicts with 5 in row 2, box 1`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units: Vec<String> = self.units().iter().map(Unit::to_string).collect();
+        write!(
+            f,
+            "{} conflicts with {} in {}",
+            self.value,
+            self.value,
+            units.join(", ")
+        )
+    }
+}
+
+/// Everything a player can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Enter a digit (1-9)
+    Fill(Coord, u8),
+    /// Erase the digit in a cell
+    Clear(Coord),
+    /// Toggle a pencil mark; with automatic candidates, remove or restore a
+    /// candidate instead
+    ToggleNote(Coord, u8),
+    /// Toggle a pencil mark in every selected cell as one step: it is added
+    /// to all of them, or removed if they all have it already
+    ToggleNotes(u8),
+    /// Remove the pencil marks of every selected cell as one step; with
+    /// automatic candidates, restore the removed candidates instead
+    ClearNotes,
+    /// Mark every selected cell with a color from 1 to [`COLORS`] as one
+    /// step, or remove the color if they all have it already; `0` removes
+    /// the colors. Colors do not affect digits or notes.
+    Color(u8),
+    /// Reveal the answer of a random cell
+    Hint,
+    Undo,
+    Redo,
+    /// Bookmark the current position in the history
+    Bookmark,
+    JumpToBookmark(usize),
+    /// Choose the branch of the history the next `Redo` follows
+    SelectBranch(usize),
+    /// Move the focus, selecting only the cell it moves to
+    MoveFocus(Direction),
+    SetFocus(Coord),
+    /// Move the focus, adding the cell it moves to to the selection
+    ExtendSelection(Direction),
+    /// Add a cell to the selection and move the focus there
+    Select(Coord),
+    /// Add the cells of a row, column or box to the selection
+    SelectUnit(Unit),
+}
+
+/// The result of an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Nothing happened, e.g. filling a given cell
+    Ignored,
+    Changed,
+    /// The digit was not entered because the cell at this coordinate already
+    /// has it. [`Game::conflict`] lists every such cell.
+    Conflict(Coord),
+    /// The action solved the puzzle
+    Completed,
+}
+
+/// A puzzle being played.
+#[derive(Debug)]
+pub struct Game {
+    ans: SudokuMatrix,
+    /// The puzzle as it was given
+    puzzle: Sudoku,
+    sudoku: Sudoku,
+    focus: Coord,
+    /// Cells acted on by `ToggleNotes` and `ClearNotes`, including the focus
+    selection: Vec<Coord>,
+    history: UndoTree,
+    undos: usize,
+    moves: usize,
+    hints: usize,
+    rating: Rating,
+    conflict: Option<Conflict>,
+    settings: Settings,
+    stopwatch: Stopwatch,
+    /// Time played before the game was saved and loaded again
+    elapsed_before: Duration,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    /// A random puzzle with a unique solution.
+    pub fn new() -> Self {
+        Self::generate(&mut Generator::new()).expect("a classic puzzle")
+    }
+
+    /// A new puzzle from a generator. Returns `None` unless the generator
+    /// makes classic 9x9 puzzles, the only ones that can be played.
+    pub fn generate(generator: &mut Generator) -> Option<Self> {
+        let puzzle = generator.generate();
+        Some(Self::with_answer(
+            puzzle.sudoku()?,
+            puzzle.solution_matrix()?,
+        ))
+    }
+
+    /// Play a given puzzle. Only its digits are used: every non-empty cell
+    /// becomes a given. Returns `None` unless it has exactly one solution.
+    pub fn from_puzzle(puzzle: &Sudoku) -> Option<Self> {
+        let ans = generator::solve_unique(puzzle)?;
+        Some(Self::with_answer(Sudoku::from_matrix(puzzle.matrix()), ans))
+    }
+
+    fn with_answer(sudoku: Sudoku, ans: SudokuMatrix) -> Self {
+        let i = (0..81)
+            .position(|x| sudoku.available[x / 9][x % 9])
+            .unwrap_or(0);
+        Self {
+            ans,
+            rating: solver::rate(&sudoku),
+            puzzle: sudoku.clone(),
+            sudoku,
+            focus: [i / 9, i % 9],
+            selection: vec![[i / 9, i % 9]],
+            moves: 0,
+            undos: 0,
+            hints: 0,
+            history: UndoTree::new(),
+            conflict: None,
+            settings: Settings::default(),
+            stopwatch: Stopwatch::new(),
+            elapsed_before: Duration::default(),
+        }
+    }
+
+    /// Reset and start the timer.
+    pub fn start(&mut self) {
+        self.stopwatch = Stopwatch::new();
+        self.stopwatch.resume();
+        self.elapsed_before = Duration::default();
+    }
+
+    pub fn pause(&mut self) {
+        self.stopwatch.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.stopwatch.resume();
+    }
+
+    pub fn apply(&mut self, action: Action) -> Event {
+        match action {
+            Action::Fill(coord, v) => self.fill(coord, v),
+            Action::Clear(coord) => self.clear(coord),
+            Action::ToggleNote(coord, v) => self.toggle_note(coord, v),
+            Action::ToggleNotes(v) if (1..=9).contains(&v) => self.mark_selection(Some(v)),
+            Action::ToggleNotes(_) => Event::Ignored,
+            Action::ClearNotes => self.mark_selection(None),
+            Action::Color(c) => self.color_selection(c),
+            Action::Hint => self.hint(),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::Bookmark => self.bookmark(),
+            Action::JumpToBookmark(k) => self.jump_to_bookmark(k),
+            Action::SelectBranch(k) => {
+                self.history.select_branch(k);
+                Event::Changed
+            }
+            Action::MoveFocus(direction) => self.move_focus(direction),
+            Action::SetFocus(coord) => self.set_focus(coord),
+            Action::ExtendSelection(direction) => {
+                let selection = std::mem::take(&mut self.selection);
+                let event = self.move_focus(direction);
+                self.selection = selection;
+                self.select(self.focus);
+                event
+            }
+            Action::Select(coord) => {
+                let selection = std::mem::take(&mut self.selection);
+                let event = self.set_focus(coord);
+                self.selection = selection;
+                if event == Event::Changed {
+                    self.select(coord);
+                }
+                event
+            }
+            Action::SelectUnit(unit) => {
+                let cells: Vec<Coord> = unit.cells().filter(|&c| self.is_available(c)).collect();
+                if cells.is_empty() {
+                    return Event::Ignored;
+                }
+                for coord in cells {
+                    self.select(coord);
+                }
+                Event::Changed
+            }
+        }
+    }
+
+    /// Show a board kept elsewhere, e.g. by the host of a cooperative game,
+    /// instead of playing on this one. The history is left alone, and so are
+    /// the colors, which are the player's own.
+    pub fn sync(&mut self, mut sudoku: Sudoku) -> Event {
+        sudoku.colors = self.sudoku.colors;
+        if sudoku == self.sudoku {
+            return Event::Ignored;
+        }
+        self.sudoku = sudoku;
+        self.conflict = None;
+        if self.sudoku.finished() {
+            self.stopwatch.pause();
+            Event::Completed
+        } else {
+            Event::Changed
+        }
+    }
+
+    /// Apply a command to the board without recording it.
+    fn run(&mut self, cmd: Command) -> Event {
+        cmd.apply(&mut self.sudoku);
+        if self.sudoku.finished() {
+            self.stopwatch.pause();
+            Event::Completed
+        } else {
+            Event::Changed
+        }
+    }
+
+    /// Apply a command and record it in the history.
+    fn execute(&mut self, cmd: Command) -> Event {
+        if cmd.is_noop() {
+            return Event::Ignored;
+        }
+        self.history.push(cmd.clone());
+        self.run(cmd)
+    }
+
+    /// With automatic candidates, changing the value of a cell recomputes the
+    /// candidates of its peers. Unless the player chose to keep them, the
+    /// candidates they removed there come back.
+    fn update_candidates(&self, mut cmd: Command) -> Command {
+        if !self.settings.auto_candidates || self.settings.keep_eliminations {
+            return cmd;
+        }
+        let coord = cmd.coord;
+        for c in std::iter::once(coord).chain(Sudoku::peers(coord)) {
+            let cell = cmd.after(c).unwrap_or_else(|| self.sudoku.cell(c));
+            if cell.eliminated != 0 {
+                cmd = cmd.with(
+                    &self.sudoku,
+                    c,
+                    Cell {
+                        eliminated: 0,
+                        ..cell
+                    },
+                );
+            }
+        }
+        cmd
+    }
+
+    fn fill(&mut self, coord: Coord, v: u8) -> Event {
+        if !self.is_available(coord) || !(1..=9).contains(&v) || self.sudoku[coord] == v {
+            return Event::Ignored;
+        }
+        self.moves += 1;
+        // with `allow_conflicts`, conflicting digits are entered like any other
+        let conflict = self.sudoku.conflict(v, coord);
+        match conflict.filter(|_| !self.settings.allow_conflicts) {
+            None => {
+                self.conflict = None;
+                let cmd = Command::fill(&self.sudoku, coord, v);
+                self.execute(self.update_candidates(cmd))
+            }
+            Some(c) => {
+                self.conflict = Some(Conflict {
+                    coord,
+                    value: v,
+                    with: self.sudoku.conflicts(v, coord),
+                });
+                Event::Conflict(c)
+            }
+        }
+    }
+
+    fn clear(&mut self, coord: Coord) -> Event {
+        if !self.is_available(coord) || self.sudoku[coord] == 0 {
+            return Event::Ignored;
+        }
+        self.moves += 1;
+        self.conflict = None;
+        let cmd = Command::clear(&self.sudoku, coord);
+        self.execute(self.update_candidates(cmd))
+    }
+
+    /// Pencil marks can only be changed in empty cells.
+    fn toggle_note(&mut self, coord: Coord, v: u8) -> Event {
+        if !self.is_available(coord) || !(1..=9).contains(&v) || self.sudoku[coord] != 0 {
+            return Event::Ignored;
+        }
+        let cmd = if self.settings.auto_candidates {
+            if self.sudoku.candidates(coord) & (1 << v) == 0 {
+                return Event::Ignored;
+            }
+            Command::toggle_elimination(&self.sudoku, coord, v)
+        } else {
+            Command::toggle_note(&self.sudoku, coord, v)
+        };
+        self.moves += 1;
+        self.execute(cmd)
+    }
+
+    /// The step changing the marks of the selected cells that are empty:
+    /// toggling `v`, or clearing them if `None`. With automatic candidates,
+    /// the candidates removed are changed instead.
+    pub fn notes_command(&self, value: Option<u8>) -> Command {
+        let auto = self.settings.auto_candidates;
+        let bit = value.map_or(0, |v| 1 << v);
+        let cells: Vec<Coord> = self
+            .selection
+            .iter()
+            .copied()
+            .filter(|&c| self.is_available(c) && self.sudoku[c] == 0)
+            // only candidates can be removed
+            .filter(|&c| !auto || bit == 0 || self.sudoku.candidates(c) & bit != 0)
+            .collect();
+        let marks = |cell: Cell| if auto { cell.eliminated } else { cell.notes };
+        let marked = cells.iter().all(|&c| marks(self.sudoku.cell(c)) & bit != 0);
+        let after: Vec<(Coord, Cell)> = cells
+            .into_iter()
+            .map(|c| {
+                let mut cell = self.sudoku.cell(c);
+                let marks = if auto {
+                    &mut cell.eliminated
+                } else {
+                    &mut cell.notes
+                };
+                *marks = match value {
+                    None => 0,
+                    Some(_) if marked => *marks & !bit,
+                    Some(_) => *marks | bit,
+                };
+                (c, cell)
+            })
+            .collect();
+        let kind = if auto {
+            CommandKind::Eliminate
+        } else {
+            CommandKind::Note
+        };
+        Command::marks(kind, &self.sudoku, &after)
+    }
+
+    fn mark_selection(&mut self, value: Option<u8>) -> Event {
+        let cmd = self.notes_command(value);
+        if cmd.is_noop() {
+            return Event::Ignored;
+        }
+        self.moves += 1;
+        self.execute(cmd)
+    }
+
+    fn color_selection(&mut self, c: u8) -> Event {
+        if c > COLORS {
+            return Event::Ignored;
+        }
+        let colored = self
+            .selection
+            .iter()
+            .all(|&coord| self.sudoku.cell(coord).color == c);
+        let color = if colored { 0 } else { c };
+        let after: Vec<(Coord, Cell)> = self
+            .selection
+            .iter()
+            .map(|&coord| {
+                (
+                    coord,
+                    Cell {
+                        color,
+                        ..self.sudoku.cell(coord)
+                    },
+                )
+            })
+            .collect();
+        let cmd = Command::marks(CommandKind::Color, &self.sudoku, &after);
+        if cmd.is_noop() {
+            return Event::Ignored;
+        }
+        self.moves += 1;
+        self.execute(cmd)
+    }
+
+    fn hint(&mut self) -> Event {
+        let mut avail = Vec::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                if self.sudoku.available[i][j] {
+                    avail.push([i, j]);
+                }
+            }
+        }
+
+        if avail.is_empty() {
+            return Event::Ignored;
+        }
+        self.hints += 1;
+        self.conflict = None;
+        let coord = avail[rand::random::<usize>() % avail.len()];
+        let [i, j] = coord;
+        let cmd = Command::hint(&self.sudoku, coord, self.ans[i][j]);
+        self.execute(self.update_candidates(cmd))
+    }
+
+    fn undo(&mut self) -> Event {
+        match self.history.undo() {
+            Some(cmd) => {
+                self.undos += 1;
+                self.moves += 1;
+                self.conflict = None;
+                self.run(cmd)
+            }
+            None => Event::Ignored,
+        }
+    }
+
+    fn redo(&mut self) -> Event {
+        match self.history.redo() {
+            Some(cmd) => {
+                self.moves += 1;
+                self.conflict = None;
+                self.run(cmd)
+            }
+            None => Event::Ignored,
+        }
+    }
+
+    fn bookmark(&mut self) -> Event {
+        let name = format!(
+            "#{} (move {})",
+            self.history.bookmarks().len() + 1,
+            self.history.depth()
+        );
+        self.history.bookmark(name);
+        Event::Changed
+    }
+
+    fn jump_to_bookmark(&mut self, k: usize) -> Event {
+        let cmds = self.history.jump_to_bookmark(k);
+        if cmds.is_empty() {
+            return Event::Ignored;
+        }
+        self.moves += 1;
+        self.conflict = None;
+        let mut event = Event::Changed;
+        for cmd in cmds {
+            event = self.run(cmd);
+        }
+        event
+    }
+
+    fn select(&mut self, coord: Coord) {
+        if !self.selection.contains(&coord) {
+            self.selection.push(coord);
+        }
+    }
+
+    fn set_focus(&mut self, coord: Coord) -> Event {
+        if self.is_available(coord) {
+            self.focus = coord;
+            self.selection = vec![coord];
+            Event::Changed
+        } else {
+            Event::Ignored
+        }
+    }
+
+    fn move_focus(&mut self, direction: Direction) -> Event {
+        let n = match direction {
+            Direction::Next | Direction::Prev => 81,
+            _ => 9,
+        };
+        match (1..n)
+            .map(|k| direction.step(self.focus, k))
+            .find(|&c| self.is_available(c))
+        {
+            Some(coord) => {
+                self.focus = coord;
+                self.selection = vec![coord];
+                Event::Changed
+            }
+            None => Event::Ignored,
+        }
+    }
+
+    /// Whether the cell can be changed: it is on the board and not a given.
+    /// Actions on other cells are ignored.
+    pub fn is_available(&self, coord: Coord) -> bool {
+        let [i, j] = coord;
+        i < 9 && j < 9 && self.sudoku.available[i][j]
+    }
+
+    /// The state of a cell; cells off the board are empty givens.
+    pub fn cell(&self, coord: Coord) -> Cell {
+        match coord {
+            [i, j] if i < 9 && j < 9 => self.sudoku.cell(coord),
+            _ => Cell::default(),
+        }
+    }
+
+    /// Automatic candidates of a cell, minus those removed by the player. Only
+    /// meaningful with `Settings::auto_candidates`.
+    pub fn candidates(&self, coord: Coord) -> u16 {
+        if !self.is_available(coord) {
+            return 0;
+        }
+        let [i, j] = coord;
+        self.sudoku.candidates(coord) & !self.sudoku.eliminated[i][j]
+    }
+
+    /// The board as it is now.
+    pub fn sudoku(&self) -> &Sudoku {
+        &self.sudoku
+    }
+
+    /// The board as it was given.
+    pub fn puzzle(&self) -> &Sudoku {
+        &self.puzzle
+    }
+
+    pub fn solution(&self) -> SudokuMatrix {
+        self.ans
+    }
+
+    /// Cells whose digit appears more than once in a row, column or box
+    pub fn errors(&self) -> Vec<Coord> {
+        self.sudoku.errors()
+    }
+
+    /// Whether the board is full and has no errors
+    pub fn is_finished(&self) -> bool {
+        self.sudoku.finished()
+    }
+
+    pub fn focus(&self) -> Coord {
+        self.focus
+    }
+
+    /// The selected cells, in the order they were selected. There is always
+    /// at least one, the focus.
+    pub fn selection(&self) -> &[Coord] {
+        &self.selection
+    }
+
+    /// Why the last digit entered was rejected, if it was.
+    pub fn conflict(&self) -> Option<&Conflict> {
+        self.conflict.as_ref()
+    }
+
+    pub fn history(&self) -> &UndoTree {
+        &self.history
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    pub fn undos(&self) -> usize {
+        self.undos
+    }
+
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// SE rating of the puzzle and the hardest technique it needs
+    pub fn rating(&self) -> Rating {
+        self.rating
+    }
+
+    /// Time spent playing, excluding pauses
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_before + self.stopwatch.read().to_std().unwrap_or_default()
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    /// The game as it is now, to be saved.
+    pub fn to_saved(&self, name: Option<String>) -> SavedGame {
+        SavedGame {
+            version: VERSION,
+            metadata: Metadata {
+                name,
+                symmetry: Symmetry::detect(&self.puzzle.matrix().concat()),
+                rating: Some(self.rating.rating).filter(|_| self.rating.solved),
+                hardest: self.rating.hardest.map(|t| t.name().to_owned()),
+            },
+            variant: Variant::Classic,
+            puzzle: self.puzzle.to_line(),
+            solution: Sudoku::from_matrix(self.ans).to_line(),
+            board: self.sudoku.clone(),
+            focus: self.focus,
+            history: self.history.clone(),
+            elapsed_ms: self.elapsed().as_millis() as u64,
+            moves: self.moves,
+            undos: self.undos,
+            hints: self.hints,
+            settings: self.settings,
+        }
+    }
+
+    /// Resume a saved game. The timer is paused until `resume` is called.
+    pub fn from_saved(saved: SavedGame) -> Result<Self, SaveError> {
+        let invalid = |e: &dyn std::fmt::Display| SaveError::Invalid(e.to_string());
+        if saved.variant != Variant::Classic {
+            return Err(invalid(&format!(
+                "{} sudoku cannot be played",
+                saved.variant
+            )));
+        }
+        let puzzle: Sudoku = saved.puzzle.parse().map_err(|e| invalid(&e))?;
+        let solution: Sudoku = saved.solution.parse().map_err(|e| invalid(&e))?;
+        if generator::solve_unique(&puzzle) != Some(solution.matrix()) {
+            return Err(invalid(&"the solution does not match the puzzle"));
+        }
+        // the givens of the board must be those of the puzzle, plus hints
+        for x in 0..81 {
+            let c = [x / 9, x % 9];
+            let given = !puzzle.available[c[0]][c[1]];
+            if given && (saved.board[c] != puzzle[c] || saved.board.available[c[0]][c[1]]) {
+                return Err(invalid(&"the board does not match the puzzle"));
+            }
+        }
+        if saved.focus[0] >= 9 || saved.focus[1] >= 9 {
+            return Err(invalid(&"focus out of range"));
+        }
+        let given = |c: &CellChange| !puzzle.available[c.coord[0]][c.coord[1]];
+        if saved
+            .history
+            .commands()
+            .any(|cmd| cmd.changes.iter().any(given))
+        {
+            return Err(invalid(&"the history changes a given"));
+        }
+        let mut game = Self::with_answer(puzzle, solution.matrix());
+        game.sudoku = saved.board;
+        game.focus = saved.focus;
+        game.selection = vec![saved.focus];
+        game.history = saved.history;
+        game.moves = saved.moves;
+        game.undos = saved.undos;
+        game.hints = saved.hints;
+        game.settings = saved.settings;
+        game.elapsed_before = Duration::from_millis(saved.elapsed_ms);
+        Ok(game)
+    }
+}
+
+/// A blank grid to type in a puzzle, e.g. one from a newspaper, before
+/// playing it.
+#[derive(Debug, Clone)]
+pub struct Editor {
+    sudoku: Sudoku,
+    focus: Coord,
+    solutions: Solutions,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self::from_puzzle(&Sudoku::from_matrix([[0; 9]; 9]))
+    }
+
+    /// Edit the digits of an existing puzzle.
+    pub fn from_puzzle(puzzle: &Sudoku) -> Self {
+        let sudoku = Sudoku::from_matrix(puzzle.matrix());
+        Self {
+            solutions: generator::solutions(&sudoku),
+            sudoku,
+            focus: [0, 0],
+        }
+    }
+
+    /// Write a digit, or erase the cell with `0`. Digits are accepted even if
+    /// they conflict with others, so that typos can be corrected in any order.
+    pub fn set(&mut self, coord: Coord, v: u8) {
+        let [i, j] = coord;
+        if i < 9 && j < 9 && v <= 9 && self.sudoku[coord] != v {
+            self.sudoku.set(coord, v);
+            self.solutions = generator::solutions(&self.sudoku);
+        }
+    }
+
+    pub fn sudoku(&self) -> &Sudoku {
+        &self.sudoku
+    }
+
+    pub fn focus(&self) -> Coord {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, coord: Coord) {
+        if coord[0] < 9 && coord[1] < 9 {
+            self.focus = coord;
+        }
+    }
+
+    pub fn move_focus(&mut self, direction: Direction) {
+        self.focus = direction.step(self.focus, 1);
+    }
+
+    /// Cells whose digit also appears in their row, column or box
+    pub fn conflicts(&self) -> Vec<Coord> {
+        (0..81)
+            .map(|x| [x / 9, x % 9])
+            .filter(|&c| {
+                let v = self.sudoku[c];
+                v != 0 && Sudoku::peers(c).any(|p| self.sudoku[p] == v)
+            })
+            .collect()
+    }
+
+    pub fn solutions(&self) -> Solutions {
+        self.solutions
+    }
+
+    /// Number of digits entered
+    pub fn clues(&self) -> usize {
+        (0..81)
+            .filter(|&x| self.sudoku[[x / 9, x % 9]] != 0)
+            .count()
+    }
+
+    /// Play the puzzle, with the digits entered as givens. Fails unless the
+    /// puzzle has a unique solution.
+    pub fn play(&self) -> Result<Game, Solutions> {
+        Game::from_puzzle(&self.sudoku).ok_or(self.solutions)
+    }
+}
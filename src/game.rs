@@ -1,83 +1,496 @@
 use crate::board::SudokuBoard;
+use crate::coop::Partner;
+use crate::engine::{Game, Settings};
+use crate::generator::Symmetry;
+use crate::pack::{Pack, Progress};
+use crate::race::Racer;
+use crate::save::{self, SaveError, SavedGame};
+use crate::tabs::Tabs;
 use cursive::{
+    event::Event,
     traits::*,
-    views::{Button, Dialog, DummyView, LinearLayout},
+    views::{Button, Checkbox, Dialog, DummyView, EditView, LinearLayout, SelectView, TextView},
     Cursive,
 };
+use hhmmss::Hhmmss;
 
 pub fn run() {
+    run_with(|_| {});
+}
+
+/// Start the game, working through a pack of puzzles.
+pub fn run_pack(pack: Pack) {
+    run_with(|board| board.load_pack(pack));
+}
+
+/// Start the game with random puzzles whose clues form a symmetric pattern.
+pub fn run_symmetric(symmetry: Symmetry) {
+    run_with(|board| board.set_symmetry(symmetry));
+}
+
+/// Continue a saved game.
+pub fn run_saved(saved: SavedGame) -> Result<(), SaveError> {
+    let game = Game::from_saved(saved)?;
+    run_with(|board| board.resume_game(game));
+    Ok(())
+}
+
+/// Race others on the puzzle of the host `racer` has joined.
+pub fn run_race(racer: Racer) {
+    run_with(|board| board.start_race(racer));
+}
+
+/// Solve the board shared by the host `partner` has joined, together with
+/// the other players.
+pub fn run_coop(partner: Partner) {
+    run_with(|board| board.start_coop(partner));
+}
+
+/// Start the game, after preparing the board with `setup`.
+fn run_with(setup: impl FnOnce(&mut SudokuBoard)) {
     let mut siv = cursive::default();
+    siv.set_fps(2);
 
+    let mut board = SudokuBoard::new();
+    board.set_progress(Progress::load());
+    setup(&mut board);
+    add_board(&mut siv, board);
+
+    siv.run();
+}
+
+/// Show `board` in `siv`, with the buttons and keys that control it.
+pub fn add_board(siv: &mut Cursive, board: SudokuBoard) {
     siv.add_global_callback('r', restart);
     siv.add_global_callback('h', hint);
     siv.add_global_callback('q', Cursive::quit);
-
-    siv.set_fps(2);
-
-    let board = SudokuBoard::new();
+    siv.add_global_callback('b', bookmarks);
+    siv.add_global_callback('t', branches);
+    siv.add_global_callback('p', packs);
+    siv.add_global_callback('s', save);
+    siv.add_global_callback('l', load);
+    siv.add_global_callback('x', export);
+    siv.add_global_callback('o', new_tab);
+    siv.add_global_callback('w', close_tab);
+    siv.add_global_callback('<', |s| cycle_tabs(s, false));
+    siv.add_global_callback('>', |s| cycle_tabs(s, true));
+    // catch up with the other players several times a second
+    siv.add_global_callback(Event::Refresh, |s| {
+        on_board(s, SudokuBoard::sync);
+    });
 
     let buttons1 = LinearLayout::horizontal()
         .child(Button::new("Restart", restart))
         .child(Button::new("Hint", hint))
         .child(Button::new("Undo", undo))
-        .child(Button::new("Redo", redo));
+        .child(Button::new("Redo", redo))
+        .child(Button::new("Packs", packs))
+        .child(Button::new("Edit", edit));
 
     let buttons2 = LinearLayout::horizontal()
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
-        .child(DummyView)
+        .child(Button::new("Mark", bookmark))
+        .child(Button::new("Jump", bookmarks))
+        .child(Button::new("Tree", branches))
+        .child(Button::new("Options", options))
         .child(DummyView)
         .child(Button::new("Help", help))
         .child(Button::new("Quit", Cursive::quit));
 
     let view = Dialog::around(
         LinearLayout::vertical()
-            .child(board.with_name("board"))
+            .child(Tabs::new(board).with_name("tabs"))
             .child(buttons1)
             .child(buttons2),
     )
     .title("SUDOKU");
 
     siv.add_layer(view);
+}
 
-    siv.run();
+/// Call `f` with the board of the selected tab.
+fn on_board<T>(s: &mut Cursive, f: impl FnOnce(&mut SudokuBoard) -> T) -> Option<T> {
+    s.call_on_name("tabs", |tabs: &mut Tabs| f(tabs.board_mut()))
+}
+
+/// Open a new game in another tab, with the settings of the current one.
+fn new_tab(s: &mut Cursive) {
+    s.call_on_name("tabs", |tabs: &mut Tabs| {
+        let mut board = SudokuBoard::new();
+        board.set_progress(Progress::load());
+        board.set_settings(tabs.board().settings());
+        tabs.open(board);
+    });
+}
+
+fn close_tab(s: &mut Cursive) {
+    s.call_on_name("tabs", |tabs: &mut Tabs| tabs.close());
+}
+
+fn cycle_tabs(s: &mut Cursive, forward: bool) {
+    s.call_on_name("tabs", |tabs: &mut Tabs| tabs.cycle(forward));
+}
+
+/// Whether the board shows the editor, which leaves the game behind it alone
+fn editing(s: &mut Cursive) -> bool {
+    on_board(s, |board| board.is_editing()).unwrap_or_default()
 }
 
 fn restart(s: &mut Cursive) {
-    s.call_on_name("board", |board: &mut SudokuBoard| {
+    if editing(s) {
+        return;
+    }
+    on_board(s, |board| {
         board.restart();
     });
 }
 
 fn hint(s: &mut Cursive) {
-    s.call_on_name("board", |board: &mut SudokuBoard| {
+    on_board(s, |board| {
         board.hint();
     });
 }
 
 fn undo(s: &mut Cursive) {
-    s.call_on_name("board", |board: &mut SudokuBoard| {
+    on_board(s, |board| {
         board.undo();
     });
 }
 
+fn edit(s: &mut Cursive) {
+    on_board(s, |board| {
+        board.edit();
+    });
+}
+
 fn redo(s: &mut Cursive) {
-    s.call_on_name("board", |board: &mut SudokuBoard| {
+    on_board(s, |board| {
         board.redo();
     });
 }
 
+fn bookmark(s: &mut Cursive) {
+    on_board(s, |board| {
+        board.bookmark();
+    });
+}
+
+/// List the bookmarks; choosing one jumps to it.
+fn bookmarks(s: &mut Cursive) {
+    if editing(s) {
+        return;
+    }
+    let names = on_board(s, |board| {
+        board
+            .bookmarks()
+            .iter()
+            .map(|b| b.name.clone())
+            .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+    if names.is_empty() {
+        s.add_layer(Dialog::info("No bookmarks yet. Press <m> to set one."));
+        return;
+    }
+    let mut select = SelectView::new();
+    for (k, name) in names.into_iter().enumerate() {
+        select.add_item(name, k);
+    }
+    select.set_on_submit(|s, &k: &usize| {
+        s.pop_layer();
+        on_board(s, |board| {
+            board.jump_to_bookmark(k);
+        });
+    });
+    s.add_layer(
+        Dialog::around(select)
+            .title("Bookmarks")
+            .dismiss_button("Cancel"),
+    );
+}
+
+/// List the branches leaving the current position; choosing one redoes it.
+fn branches(s: &mut Cursive) {
+    if editing(s) {
+        return;
+    }
+    let (branches, active) =
+        on_board(s, |board| (board.branches(), board.active_branch())).unwrap_or_default();
+    if branches.is_empty() {
+        s.add_layer(Dialog::info("Nothing to redo from here."));
+        return;
+    }
+    let mut select = SelectView::new();
+    for (k, cmd) in branches.into_iter().enumerate() {
+        select.add_item(cmd.to_string(), k);
+    }
+    select.set_selection(active);
+    select.set_on_submit(|s, &k: &usize| {
+        s.pop_layer();
+        on_board(s, |board| {
+            board.select_branch(k);
+            board.redo();
+        });
+    });
+    s.add_layer(
+        Dialog::around(select)
+            .title("Branches")
+            .dismiss_button("Cancel"),
+    );
+}
+
+fn options(s: &mut Cursive) {
+    let settings = on_board(s, |board| board.settings()).unwrap_or_default();
+    let checkbox = |label: &str, name: &str, checked: bool| {
+        LinearLayout::horizontal()
+            .child(Checkbox::new().with_checked(checked).with_name(name))
+            .child(TextView::new(format!(" {}", label)))
+    };
+    let list = LinearLayout::vertical()
+        .child(checkbox(
+            "Automatic candidates",
+            "auto_candidates",
+            settings.auto_candidates,
+        ))
+        .child(checkbox(
+            "Show candidates",
+            "show_candidates",
+            settings.show_candidates,
+        ))
+        .child(checkbox(
+            "Keep removed candidates",
+            "keep_eliminations",
+            settings.keep_eliminations,
+        ))
+        .child(checkbox(
+            "Allow conflicting digits",
+            "allow_conflicts",
+            settings.allow_conflicts,
+        ));
+    s.add_layer(
+        Dialog::around(list)
+            .title("Options")
+            .button("Ok", |s| {
+                let mut checked = |name: &str| {
+                    s.call_on_name(name, |c: &mut Checkbox| c.is_checked())
+                        .unwrap_or_default()
+                };
+                let settings = Settings {
+                    auto_candidates: checked("auto_candidates"),
+                    show_candidates: checked("show_candidates"),
+                    keep_eliminations: checked("keep_eliminations"),
+                    allow_conflicts: checked("allow_conflicts"),
+                };
+                on_board(s, |board| {
+                    board.set_settings(settings);
+                });
+                s.pop_layer();
+            })
+            .dismiss_button("Cancel"),
+    );
+}
+
+/// Browse the loaded pack, or open one if there is none.
+fn packs(s: &mut Cursive) {
+    let loaded = on_board(s, |board| board.pack().is_some()).unwrap_or_default();
+    if loaded {
+        pack_browser(s);
+    } else {
+        open_pack(s);
+    }
+}
+
+fn save(s: &mut Cursive) {
+    let submit = |s: &mut Cursive, path: &str| {
+        let saved = on_board(s, |board| board.save_game()).unwrap();
+        s.pop_layer();
+        match saved.save(path) {
+            Ok(()) => s.add_layer(Dialog::info(format!("Saved to {}", path))),
+            Err(e) => s.add_layer(Dialog::info(format!("Cannot save to {}: {}", path, e))),
+        }
+    };
+    s.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new("Save to:"))
+                .child(
+                    EditView::new()
+                        .content(save::default_path().to_string_lossy())
+                        .on_submit(submit)
+                        .with_name("save_path")
+                        .fixed_width(40),
+                ),
+        )
+        .title("Save Game")
+        .button("Save", move |s| {
+            let path = s
+                .call_on_name("save_path", |v: &mut EditView| v.get_content())
+                .unwrap();
+            submit(s, &path);
+        })
+        .dismiss_button("Cancel"),
+    );
+}
+
+fn load(s: &mut Cursive) {
+    let submit = |s: &mut Cursive, path: &str| {
+        let loaded = SavedGame::load(path)
+            .and_then(|saved| on_board(s, |board| board.load_game(saved)).unwrap());
+        match loaded {
+            Ok(()) => {
+                s.pop_layer();
+            }
+            Err(e) => s.add_layer(Dialog::info(format!("Cannot load {}: {}", path, e))),
+        }
+    };
+    s.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new("Load from:"))
+                .child(
+                    EditView::new()
+                        .content(save::default_path().to_string_lossy())
+                        .on_submit(submit)
+                        .with_name("load_path")
+                        .fixed_width(40),
+                ),
+        )
+        .title("Load Game")
+        .button("Load", move |s| {
+            let path = s
+                .call_on_name("load_path", |v: &mut EditView| v.get_content())
+                .unwrap();
+            submit(s, &path);
+        })
+        .dismiss_button("Cancel"),
+    );
+}
+
+/// Write the puzzle, or the whole pack, as a printable PDF or PostScript
+/// sheet.
+fn export(s: &mut Cursive) {
+    let has_pack = on_board(s, |board| board.pack().is_some()).unwrap_or_default();
+    let submit = |s: &mut Cursive, path: &str| {
+        let mut checked = |name: &str| {
+            s.call_on_name(name, |c: &mut Checkbox| c.is_checked())
+                .unwrap_or_default()
+        };
+        let (solutions, whole_pack) = (checked("export_solutions"), checked("export_pack"));
+        let sheet = on_board(s, |board| board.sheet(whole_pack))
+            .unwrap()
+            .per_page(if whole_pack { 4 } else { 1 })
+            .solutions(solutions);
+        s.pop_layer();
+        match sheet.save(path) {
+            Ok(()) => s.add_layer(Dialog::info(format!("Wrote {}", path))),
+            Err(e) => s.add_layer(Dialog::info(format!("Cannot write {}: {}", path, e))),
+        }
+    };
+    let checkbox = |label: &str, name: &str| {
+        LinearLayout::horizontal()
+            .child(Checkbox::new().with_name(name))
+            .child(TextView::new(format!(" {}", label)))
+    };
+    let mut list = LinearLayout::vertical()
+        .child(TextView::new("Write to (.pdf or .ps):"))
+        .child(
+            EditView::new()
+                .content("sudoku.pdf")
+                .on_submit(submit)
+                .with_name("export_path")
+                .fixed_width(40),
+        )
+        .child(checkbox("Include solutions", "export_solutions"));
+    if has_pack {
+        list.add_child(checkbox("All puzzles in the pack", "export_pack"));
+    }
+    s.add_layer(
+        Dialog::around(list)
+            .title("Print")
+            .button("Write", move |s| {
+                let path = s
+                    .call_on_name("export_path", |v: &mut EditView| v.get_content())
+                    .unwrap();
+                submit(s, &path);
+            })
+            .dismiss_button("Cancel"),
+    );
+}
+
+fn open_pack(s: &mut Cursive) {
+    let submit = |s: &mut Cursive, path: &str| {
+        match Pack::load(path) {
+            Ok(pack) => {
+                s.pop_layer();
+                on_board(s, |board| board.load_pack(pack));
+                pack_browser(s);
+            }
+            Err(e) => s.add_layer(Dialog::info(format!("Cannot open {}: {}", path, e))),
+        };
+    };
+    s.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new("Pack file (one puzzle per line):"))
+                .child(
+                    EditView::new()
+                        .on_submit(submit)
+                        .with_name("pack_path")
+                        .fixed_width(40),
+                ),
+        )
+        .title("Open Pack")
+        .button("Open", move |s| {
+            let path = s
+                .call_on_name("pack_path", |v: &mut EditView| v.get_content())
+                .unwrap();
+            submit(s, &path);
+        })
+        .dismiss_button("Cancel"),
+    );
+}
+
+/// List the puzzles of the pack with their completion status and best times;
+/// choosing one starts it.
+fn pack_browser(s: &mut Cursive) {
+    let mut select = SelectView::new();
+    let mut title = String::new();
+    on_board(s, |board| {
+        let pack = match board.pack() {
+            Some(pack) => pack,
+            None => return,
+        };
+        title = pack.name.clone();
+        for (k, entry) in pack.entries.iter().enumerate() {
+            let best = board.progress().best(&entry.puzzle);
+            let rating = entry
+                .rating
+                .map_or_else(|| "   -".to_owned(), |r| format!("{:4.1}", r));
+            let label = format!(
+                "{:>3} {} {:<20.20} {} {}",
+                k + 1,
+                if best.is_some() { "✓" } else { " " },
+                entry.name.as_deref().unwrap_or(""),
+                rating,
+                best.map_or_else(String::new, |t| t.hhmmss()),
+            );
+            select.add_item(label, k);
+        }
+    });
+    select.set_on_submit(|s, &k: &usize| {
+        s.pop_layer();
+        on_board(s, |board| board.play_pack_puzzle(k));
+    });
+    s.add_layer(
+        Dialog::around(select.scrollable().max_height(15))
+            .title(title)
+            .button("Open...", |s| {
+                s.pop_layer();
+                open_pack(s);
+            })
+            .dismiss_button("Cancel"),
+    );
+}
+
 fn help(s: &mut Cursive) {
-    s.add_layer(Dialog::info("Use arrow keys/TAB/Shift+TAB/mouse wheel/mouse click to navigate.\nEnter number 0-9 to fill in.\nClick <Hint> or press <h> to obtain a hint.\nGood luck."))
+    s.add_layer(Dialog::info("Use arrow keys/TAB/Shift+TAB/mouse wheel/mouse click to navigate.\nEnter number 1-9 to fill in, 0/Backspace/Delete to erase.\nOr play with the mouse: click the digits, <Del> and <Note> below the board, drag over cells to mark or erase several at once, and right-click a cell to cycle its pencil mark.\nSelect several cells with Shift+arrow keys or by dragging the mouse, or the whole row, column or box with <R>, <C> or <B>; in note mode, a digit then toggles that pencil mark in all of them and 0 clears their marks, as one undoable step.\nPress Alt+1 to Alt+6 to mark the selected cells with a color (again to remove it, Alt+0 to remove any), e.g. for coloring and chains; colors are saved and can be undone. Without Alt, press <k> or click <Color> to switch to entering colors with 1-6 and 0.\nThe numbers under the palette count how many of each digit are still missing; digits that are all placed are greyed out, and clicking a count highlights that digit on the board.\nPress <n> to switch between entering answers and pencil marks.\nPress <a> to let the board compute candidates automatically and <c> to show/hide them; in this mode pencil marks remove candidates.\nClick <Hint> or press <h> to obtain a hint.\nPress <m> to bookmark the current position (e.g. before a guess) and <b> to jump back to a bookmark.\nMoves made after undoing start a new branch; use [ and ] to choose the branch to redo, or <t> to list them.\nClick <Packs> or press <p> to play a collection of puzzles in order.\nOn the start screen, use the left and right arrow keys to choose the symmetry of the clues.\nClick <Edit> or press <e> on the start screen to type in a puzzle; press <Enter> to play it once it has a unique solution, or <Esc> to go back.\nPress <s> to save the game and <l> to load a saved game.\nPress <x> to write the puzzle (or the whole pack) as a PDF or PostScript file for printing.\nPress <v> on the start screen for two players taking turns on one board: correct digits score points, wrong ones cost points and are not placed.\nPress <o> to open another game in a new tab, < and > (or a click on a tab) to switch between them, and <w> to close one; hidden games are paused.\nGood luck."))
 }
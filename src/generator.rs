@@ -0,0 +1,748 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Puzzle generation.
+//!
+//! A puzzle is made by filling an empty grid at random, then removing clues
+//! (a whole group of cells at a time, according to the [`Symmetry`]) for as
+//! long as the solution stays unique. Classic 9x9 puzzles can be made to match
+//! a [`Difficulty`], by generating puzzles until one has a suitable rating.
+//!
+//! ```
+//! use sudoku_tui::generator::{Difficulty, Generator};
+//!
+//! let puzzle = Generator::new().seed(42).difficulty(Difficulty::Easy).generate();
+//! let sudoku = puzzle.sudoku().unwrap();
+//! assert_eq!(sudoku_tui::generator::solve_unique(&sudoku), Some(puzzle.solution_matrix().unwrap()));
+//! ```
+
+use crate::solver;
+use crate::sudoku::{Sudoku, SudokuMatrix};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Dimensions of the board: `box_width * box_height` digits, rows and columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Shape {
+    pub box_height: usize,
+    pub box_width: usize,
+}
+
+impl Shape {
+    pub const CLASSIC: Self = Self {
+        box_height: 3,
+        box_width: 3,
+    };
+
+    /// Boxes of `box_height` rows by `box_width` columns. Returns `None` for
+    /// empty boxes and boards larger than 9x9, which are not supported.
+    pub fn new(box_height: usize, box_width: usize) -> Option<Self> {
+        let size = box_height.checked_mul(box_width)?;
+        if box_height == 0 || box_width == 0 || size > 9 {
+            return None;
+        }
+        Some(Self {
+            box_height,
+            box_width,
+        })
+    }
+
+    /// Number of rows, columns and digits
+    pub fn size(self) -> usize {
+        self.box_height * self.box_width
+    }
+
+    pub fn cells(self) -> usize {
+        self.size() * self.size()
+    }
+
+    /// The board with `size` rows, and boxes as square as possible, wider than
+    /// they are high (e.g. 2x3 boxes for size 6). Returns `None` for prime
+    /// sizes, whose only boxes would be the rows themselves.
+    // `usize::is_multiple_of` needs a newer compiler than the crate supports
+    #[allow(unknown_lints, clippy::manual_is_multiple_of)]
+    pub fn with_size(size: usize) -> Option<Self> {
+        if size > 9 {
+            return None;
+        }
+        let height = (2..=size)
+            .filter(|h| size % h == 0 && h * h <= size)
+            .max()?;
+        Self::new(height, size / height)
+    }
+}
+
+impl std::str::FromStr for Shape {
+    type Err = ParseOptionError;
+
+    /// Read a size such as `6` or `6x6`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let size = match s.split_once('x') {
+            Some((a, b)) if a == b => a,
+            _ => s,
+        };
+        size.parse()
+            .ok()
+            .and_then(Self::with_size)
+            .ok_or_else(|| ParseOptionError(s.to_owned()))
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.size(), self.size())
+    }
+}
+
+/// Extra rules on top of rows, columns and boxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    #[default]
+    Classic,
+    /// Both main diagonals must also contain every digit (X-Sudoku)
+    Diagonal,
+}
+
+impl Variant {
+    pub const ALL: [Self; 2] = [Self::Classic, Self::Diagonal];
+}
+
+impl std::str::FromStr for Variant {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|variant| variant.to_string() == s)
+            .ok_or_else(|| ParseOptionError(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Classic => write!(f, "classic"),
+            Self::Diagonal => write!(f, "diagonal"),
+        }
+    }
+}
+
+/// Which cells are emptied together, so that the clues form a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// The clues look the same after turning the board upside down
+    Rotational,
+    /// The bottom half mirrors the top half
+    Horizontal,
+    /// The right half mirrors the left half
+    Vertical,
+    /// Mirrored along the main diagonal, from top left to bottom right
+    Diagonal,
+    /// All of the above, as well as quarter turns and the anti-diagonal
+    Dihedral,
+}
+
+/// Maps row `i` and column `j` of a board `n` cells wide to another cell.
+type Transform = fn(usize, usize, usize) -> (usize, usize);
+
+impl Symmetry {
+    pub const ALL: [Self; 6] = [
+        Self::None,
+        Self::Rotational,
+        Self::Horizontal,
+        Self::Vertical,
+        Self::Diagonal,
+        Self::Dihedral,
+    ];
+
+    /// The transformations of the board which map the clues onto themselves.
+    fn transforms(self) -> &'static [Transform] {
+        match self {
+            Self::None => &[],
+            Self::Rotational => &[|i, j, n| (n - 1 - i, n - 1 - j)],
+            Self::Horizontal => &[|i, j, n| (n - 1 - i, j)],
+            Self::Vertical => &[|i, j, n| (i, n - 1 - j)],
+            Self::Diagonal => &[|i, j, _| (j, i)],
+            // these generate the whole group
+            Self::Dihedral => &[|i, j, _| (j, i), |i, j, n| (n - 1 - i, j)],
+        }
+    }
+
+    /// The cells emptied along with cell `x` of a board `n` cells wide
+    fn orbit(self, n: usize, x: usize) -> Vec<usize> {
+        let mut orbit = vec![x];
+        let mut k = 0;
+        while k < orbit.len() {
+            let (i, j) = (orbit[k] / n, orbit[k] % n);
+            for f in self.transforms() {
+                let (i, j) = f(i, j, n);
+                if !orbit.contains(&(i * n + j)) {
+                    orbit.push(i * n + j);
+                }
+            }
+            k += 1;
+        }
+        orbit.sort_unstable();
+        orbit
+    }
+
+    /// The most symmetric pattern formed by the clues of a square board, given
+    /// in reading order with `0` for empty cells.
+    pub fn detect(givens: &[u8]) -> Self {
+        let n = (givens.len() as f64).sqrt() as usize;
+        let fits = |symmetry: Self| {
+            (0..n * n).all(|x| {
+                symmetry
+                    .orbit(n, x)
+                    .iter()
+                    .all(|&y| (givens[x] == 0) == (givens[y] == 0))
+            })
+        };
+        [
+            Self::Dihedral,
+            Self::Rotational,
+            Self::Diagonal,
+            Self::Horizontal,
+            Self::Vertical,
+        ]
+        .iter()
+        .copied()
+        .find(|&symmetry| fits(symmetry))
+        .unwrap_or(Self::None)
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Rotational => write!(f, "rotational"),
+            Self::Horizontal => write!(f, "horizontal"),
+            Self::Vertical => write!(f, "vertical"),
+            Self::Diagonal => write!(f, "diagonal"),
+            Self::Dihedral => write!(f, "dihedral"),
+        }
+    }
+}
+
+impl std::str::FromStr for Symmetry {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|symmetry| symmetry.to_string() == s)
+            .ok_or_else(|| ParseOptionError(s.to_owned()))
+    }
+}
+
+/// An unknown name of a generator option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptionError(pub String);
+
+impl fmt::Display for ParseOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid value '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseOptionError {}
+
+/// Target ranges of SE rating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// Hidden singles only
+    Easy,
+    /// Naked singles
+    Medium,
+    /// Locked candidates, pairs and X-Wings
+    Hard,
+    /// Anything harder
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Self; 4] = [Self::Easy, Self::Medium, Self::Hard, Self::Expert];
+
+    /// Whether a puzzle with this rating is of this difficulty
+    pub fn contains(self, rating: &solver::Rating) -> bool {
+        let r = rating.rating;
+        match self {
+            Self::Easy => rating.solved && r <= 1.5,
+            Self::Medium => rating.solved && r > 1.5 && r <= 2.3,
+            Self::Hard => rating.solved && r > 2.3 && r <= 3.4,
+            Self::Expert => !rating.solved || r > 3.4,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Easy => write!(f, "easy"),
+            Self::Medium => write!(f, "medium"),
+            Self::Hard => write!(f, "hard"),
+            Self::Expert => write!(f, "expert"),
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.to_string() == s)
+            .ok_or_else(|| ParseOptionError(s.to_owned()))
+    }
+}
+
+/// The rows, columns, boxes and any extra regions of a board, as lists of
+/// cell indices, and the regions each cell belongs to.
+#[derive(Debug, Clone)]
+struct Units {
+    size: usize,
+    units: Vec<Vec<usize>>,
+    of_cell: Vec<Vec<usize>>,
+}
+
+impl Units {
+    fn new(shape: Shape, variant: Variant) -> Self {
+        let n = shape.size();
+        let (h, w) = (shape.box_height, shape.box_width);
+        let mut units: Vec<Vec<usize>> = Vec::new();
+        for a in 0..n {
+            units.push((0..n).map(|b| a * n + b).collect());
+            units.push((0..n).map(|b| b * n + a).collect());
+            // boxes are numbered left to right, top to bottom
+            let (top, left) = (a / h * h, a % h * w);
+            units.push((0..n).map(|b| (top + b / w) * n + left + b % w).collect());
+        }
+        if variant == Variant::Diagonal {
+            units.push((0..n).map(|a| a * n + a).collect());
+            units.push((0..n).map(|a| a * n + n - 1 - a).collect());
+        }
+        let mut of_cell = vec![Vec::new(); n * n];
+        for (u, unit) in units.iter().enumerate() {
+            for &x in unit {
+                of_cell[x].push(u);
+            }
+        }
+        Self {
+            size: n,
+            units,
+            of_cell,
+        }
+    }
+}
+
+/// Backtracking search over the digits of a board, one per cell (`0` if
+/// empty).
+struct Search<'a> {
+    units: &'a Units,
+    cells: Vec<u8>,
+    /// Digits used in each unit
+    used: Vec<u16>,
+    /// Try digits in random order, to generate filled grids
+    rng: Option<&'a mut StdRng>,
+    limit: usize,
+    count: usize,
+    solution: Option<Vec<u8>>,
+}
+
+impl<'a> Search<'a> {
+    /// `None` if two cells of a unit have the same digit.
+    fn new(units: &'a Units, cells: &[u8], limit: usize) -> Option<Self> {
+        let mut used = vec![0u16; units.units.len()];
+        for (x, &v) in cells.iter().enumerate().filter(|(_, &v)| v != 0) {
+            for &u in &units.of_cell[x] {
+                if used[u] & (1 << v) != 0 {
+                    return None;
+                }
+                used[u] |= 1 << v;
+            }
+        }
+        Some(Self {
+            units,
+            cells: cells.to_vec(),
+            used,
+            rng: None,
+            limit,
+            count: 0,
+            solution: None,
+        })
+    }
+
+    fn candidates(&self, x: usize) -> u16 {
+        let all = ((1 << (self.units.size + 1)) - 2) as u16;
+        self.units.of_cell[x]
+            .iter()
+            .fold(all, |mask, &u| mask & !self.used[u])
+    }
+
+    fn place(&mut self, x: usize, v: u8) {
+        for &u in &self.units.of_cell[x] {
+            self.used[u] |= 1 << v;
+        }
+        self.cells[x] = v;
+    }
+
+    fn unplace(&mut self, x: usize, v: u8) {
+        for &u in &self.units.of_cell[x] {
+            self.used[u] &= !(1 << v);
+        }
+        self.cells[x] = 0;
+    }
+
+    /// Count solutions (up to `limit`), keeping the first one found.
+    fn run(&mut self) {
+        // the empty cell with the fewest candidates
+        let mut best = None;
+        for x in (0..self.cells.len()).filter(|&x| self.cells[x] == 0) {
+            let n = self.candidates(x).count_ones();
+            if best.is_none_or(|(_, m)| n < m) {
+                best = Some((x, n));
+                if n <= 1 {
+                    break;
+                }
+            }
+        }
+        let x = match best {
+            Some((x, _)) => x,
+            None => {
+                self.count += 1;
+                if self.solution.is_none() {
+                    self.solution = Some(self.cells.clone());
+                }
+                return;
+            }
+        };
+        let mask = self.candidates(x);
+        let mut digits: Vec<u8> = (1..=self.units.size as u8)
+            .filter(|v| mask & (1 << v) != 0)
+            .collect();
+        if let Some(rng) = self.rng.as_mut() {
+            digits.shuffle(rng);
+        }
+        for v in digits {
+            self.place(x, v);
+            self.run();
+            self.unplace(x, v);
+            if self.count >= self.limit {
+                return;
+            }
+        }
+    }
+}
+
+/// Number of solutions of a board, counting no further than `limit`.
+fn count_solutions(units: &Units, cells: &[u8], limit: usize) -> usize {
+    match Search::new(units, cells, limit) {
+        Some(mut search) => {
+            search.run();
+            search.count
+        }
+        None => 0,
+    }
+}
+
+/// The solution of a classic puzzle, unless it has none or several.
+pub fn solve_unique(sudoku: &Sudoku) -> Option<SudokuMatrix> {
+    let units = Units::new(Shape::CLASSIC, Variant::Classic);
+    let cells: Vec<u8> = sudoku.matrix().iter().flatten().copied().collect();
+    let mut search = Search::new(&units, &cells, 2)?;
+    search.run();
+    if search.count != 1 {
+        return None;
+    }
+    let solution = search.solution?;
+    let mut matrix = [[0; 9]; 9];
+    for (x, v) in solution.into_iter().enumerate() {
+        matrix[x / 9][x % 9] = v;
+    }
+    Some(matrix)
+}
+
+/// How many solutions a puzzle has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solutions {
+    None,
+    Unique,
+    /// Two or more
+    Multiple,
+}
+
+impl fmt::Display for Solutions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Unique => write!(f, "unique"),
+            Self::Multiple => write!(f, "multiple"),
+        }
+    }
+}
+
+/// Whether a classic puzzle has no solution, one, or several.
+pub fn solutions(sudoku: &Sudoku) -> Solutions {
+    let units = Units::new(Shape::CLASSIC, Variant::Classic);
+    let cells: Vec<u8> = sudoku.matrix().iter().flatten().copied().collect();
+    match count_solutions(&units, &cells, 2) {
+        0 => Solutions::None,
+        1 => Solutions::Unique,
+        _ => Solutions::Multiple,
+    }
+}
+
+/// A generated puzzle and its solution, as digits in reading order (`0` for
+/// empty cells).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub shape: Shape,
+    pub variant: Variant,
+    pub symmetry: Symmetry,
+    pub givens: Vec<u8>,
+    pub solution: Vec<u8>,
+}
+
+impl Puzzle {
+    /// A classic puzzle, if it has a unique solution.
+    pub fn from_sudoku(sudoku: &Sudoku) -> Option<Self> {
+        let solution = solve_unique(sudoku)?;
+        let givens: Vec<u8> = sudoku.matrix().concat();
+        Some(Self {
+            shape: Shape::CLASSIC,
+            variant: Variant::Classic,
+            symmetry: Symmetry::detect(&givens),
+            givens,
+            solution: solution.concat(),
+        })
+    }
+
+    /// The puzzle as a playable board, if it is a classic 9x9 puzzle.
+    pub fn sudoku(&self) -> Option<Sudoku> {
+        self.to_matrix(&self.givens).map(Sudoku::from_matrix)
+    }
+
+    /// The solution, if it is a classic 9x9 puzzle.
+    pub fn solution_matrix(&self) -> Option<SudokuMatrix> {
+        self.to_matrix(&self.solution)
+    }
+
+    fn to_matrix(&self, cells: &[u8]) -> Option<SudokuMatrix> {
+        if self.shape != Shape::CLASSIC || self.variant != Variant::Classic {
+            return None;
+        }
+        let mut matrix = [[0; 9]; 9];
+        for (x, &v) in cells.iter().enumerate() {
+            matrix[x / 9][x % 9] = v;
+        }
+        Some(matrix)
+    }
+
+    /// The puzzle as a line of digits, with `.` for empty cells.
+    pub fn to_line(&self) -> String {
+        self.givens
+            .iter()
+            .map(|&v| if v == 0 { '.' } else { (b'0' + v) as char })
+            .collect()
+    }
+
+    /// Number of clues
+    pub fn clues(&self) -> usize {
+        self.givens.iter().filter(|&&v| v != 0).count()
+    }
+}
+
+/// Generates puzzles with a unique solution.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    shape: Shape,
+    variant: Variant,
+    symmetry: Symmetry,
+    difficulty: Option<Difficulty>,
+    rng: StdRng,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator {
+    /// Classic 9x9 puzzles of any difficulty, without symmetry.
+    pub fn new() -> Self {
+        Self {
+            shape: Shape::CLASSIC,
+            variant: Variant::Classic,
+            symmetry: Symmetry::None,
+            difficulty: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn shape(mut self, shape: Shape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Only classic 9x9 puzzles can be rated, so the difficulty is ignored for
+    /// other shapes and variants.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Generate the same puzzles every time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// A random, completely filled grid.
+    pub fn filled(&mut self) -> Vec<u8> {
+        let units = Units::new(self.shape, self.variant);
+        let empty = vec![0; self.shape.cells()];
+        let mut search = Search::new(&units, &empty, 1).unwrap();
+        search.rng = Some(&mut self.rng);
+        search.run();
+        search.solution.expect("every shape can be filled")
+    }
+
+    /// A puzzle of the requested difficulty. If none is found after a number
+    /// of attempts, the closest one is returned instead.
+    pub fn generate(&mut self) -> Puzzle {
+        const ATTEMPTS: usize = 200;
+        let difficulty = match self.difficulty {
+            Some(d) if self.shape == Shape::CLASSIC && self.variant == Variant::Classic => d,
+            _ => return self.attempt(),
+        };
+        let mut closest: Option<(f32, Puzzle)> = None;
+        for _ in 0..ATTEMPTS {
+            let puzzle = self.attempt();
+            let rating = solver::rate(&puzzle.sudoku().unwrap());
+            if difficulty.contains(&rating) {
+                return puzzle;
+            }
+            let distance = Self::distance(difficulty, &rating);
+            if closest.as_ref().is_none_or(|(d, _)| distance < *d) {
+                closest = Some((distance, puzzle));
+            }
+        }
+        closest.unwrap().1
+    }
+
+    /// How far a rating is from a difficulty, for puzzles not matching it
+    fn distance(difficulty: Difficulty, rating: &solver::Rating) -> f32 {
+        let r = if rating.solved { rating.rating } else { 10.0 };
+        let (low, high) = match difficulty {
+            Difficulty::Easy => (0.0, 1.5),
+            Difficulty::Medium => (1.5, 2.3),
+            Difficulty::Hard => (2.3, 3.4),
+            Difficulty::Expert => (3.4, 10.0),
+        };
+        (low - r).max(r - high).max(0.0)
+    }
+
+    /// Fill a grid and remove as many clues as possible.
+    fn attempt(&mut self) -> Puzzle {
+        let units = Units::new(self.shape, self.variant);
+        let solution = self.filled();
+        let mut givens = solution.clone();
+        let n = self.shape.size();
+        let mut cells: Vec<usize> = (0..n * n).collect();
+        cells.shuffle(&mut self.rng);
+        for x in cells {
+            if givens[x] == 0 {
+                continue;
+            }
+            let orbit = self.symmetry.orbit(n, x);
+            let kept: Vec<u8> = orbit.iter().map(|&y| givens[y]).collect();
+            for &y in &orbit {
+                givens[y] = 0;
+            }
+            if count_solutions(&units, &givens, 2) != 1 {
+                for (&y, &v) in orbit.iter().zip(&kept) {
+                    givens[y] = v;
+                }
+            }
+        }
+        Puzzle {
+            shape: self.shape,
+            variant: self.variant,
+            symmetry: self.symmetry,
+            givens,
+            solution,
+        }
+    }
+}
+
+/// Generate `count` puzzles like `generator` on `threads` threads, calling
+/// `progress` with the number of puzzles done so far.
+///
+/// The puzzles only depend on `seed`, not on the number of threads.
+pub fn batch(
+    generator: &Generator,
+    seed: u64,
+    count: usize,
+    threads: usize,
+    mut progress: impl FnMut(usize),
+) -> Vec<Puzzle> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let seeds: Vec<u64> = (0..count).map(|_| rng.gen()).collect();
+    let next = AtomicUsize::new(0);
+    let mut puzzles = vec![None; count];
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let (tx, next, seeds) = (tx.clone(), &next, &seeds);
+            scope.spawn(move || loop {
+                let k = next.fetch_add(1, Ordering::Relaxed);
+                if k >= count {
+                    break;
+                }
+                let puzzle = generator.clone().seed(seeds[k]).generate();
+                if tx.send((k, puzzle)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+        for (done, (k, puzzle)) in rx.iter().enumerate() {
+            puzzles[k] = Some(puzzle);
+            progress(done + 1);
+        }
+    });
+    puzzles.into_iter().map(Option::unwrap).collect()
+}
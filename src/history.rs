@@ -0,0 +1,508 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Branching undo history.
+//!
+//! Making a move after undoing does not throw away the moves that were undone;
+//! they are kept as a sibling branch which can be revisited later, either by
+//! selecting it before redoing or by jumping to a bookmark.
+//!
+//! Every change to the board goes through a [`Command`], so fills, erasures,
+//! pencil marks and hints can all be undone in the same way.
+
+use crate::sudoku::{Cell, Coord, Sudoku, COLORS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandKind {
+    Fill,
+    Clear,
+    Note,
+    Eliminate,
+    Hint,
+    /// Marking cells with a color
+    Color,
+}
+
+/// The change of one cell, recording its complete state before and after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellChange {
+    pub coord: Coord,
+    pub before: Cell,
+    pub after: Cell,
+}
+
+/// One undoable step: a change of the cell at `coord`, together with any
+/// changes to other cells which it caused.
+///
+/// Because the state of every cell is stored before and after, applying a
+/// command or its inverse does not depend on what the board looks like at the
+/// time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Command {
+    pub kind: CommandKind,
+    pub coord: Coord,
+    pub changes: Vec<CellChange>,
+}
+
+impl Command {
+    fn new(kind: CommandKind, sudoku: &Sudoku, coord: Coord, after: Cell) -> Self {
+        Self {
+            kind,
+            coord,
+            changes: vec![CellChange {
+                coord,
+                before: sudoku.cell(coord),
+                after,
+            }],
+        }
+    }
+
+    /// Enter `v` into the cell.
+    pub fn fill(sudoku: &Sudoku, coord: Coord, v: u8) -> Self {
+        let after = Cell {
+            value: v,
+            ..sudoku.cell(coord)
+        };
+        Self::new(CommandKind::Fill, sudoku, coord, after)
+    }
+
+    /// Erase the digit in the cell, keeping its notes.
+    pub fn clear(sudoku: &Sudoku, coord: Coord) -> Self {
+        let after = Cell {
+            value: 0,
+            ..sudoku.cell(coord)
+        };
+        Self::new(CommandKind::Clear, sudoku, coord, after)
+    }
+
+    /// Add or remove the pencil mark `v`.
+    pub fn toggle_note(sudoku: &Sudoku, coord: Coord, v: u8) -> Self {
+        let mut after = sudoku.cell(coord);
+        after.notes ^= 1 << v;
+        Self::new(CommandKind::Note, sudoku, coord, after)
+    }
+
+    /// Remove the automatic candidate `v`, or bring it back.
+    pub fn toggle_elimination(sudoku: &Sudoku, coord: Coord, v: u8) -> Self {
+        let mut after = sudoku.cell(coord);
+        after.eliminated ^= 1 << v;
+        Self::new(CommandKind::Eliminate, sudoku, coord, after)
+    }
+
+    /// Change the pencil marks (`Note`), removed candidates (`Eliminate`) or
+    /// colors (`Color`) of several cells as one step, given the state of each
+    /// afterwards. Cells left as they are are not recorded.
+    pub fn marks(kind: CommandKind, sudoku: &Sudoku, cells: &[(Coord, Cell)]) -> Self {
+        let changes: Vec<CellChange> = cells
+            .iter()
+            .map(|&(coord, after)| CellChange {
+                coord,
+                before: sudoku.cell(coord),
+                after,
+            })
+            .filter(|c| c.before != c.after)
+            .collect();
+        Self {
+            kind,
+            coord: changes.first().map_or([0, 0], |c| c.coord),
+            changes,
+        }
+    }
+
+    /// Reveal the answer `v`, turning the cell into a given.
+    pub fn hint(sudoku: &Sudoku, coord: Coord, v: u8) -> Self {
+        let after = Cell {
+            value: v,
+            available: false,
+            color: sudoku.cell(coord).color,
+            ..Cell::default()
+        };
+        Self::new(CommandKind::Hint, sudoku, coord, after)
+    }
+
+    /// Also change the cell at `coord` to `after` as part of this step.
+    pub fn with(mut self, sudoku: &Sudoku, coord: Coord, after: Cell) -> Self {
+        match self.changes.iter_mut().find(|c| c.coord == coord) {
+            Some(change) => change.after = after,
+            None => self.changes.push(CellChange {
+                coord,
+                before: sudoku.cell(coord),
+                after,
+            }),
+        }
+        self
+    }
+
+    /// The state of the cell at `coord` after this command, if it changes it.
+    pub fn after(&self, coord: Coord) -> Option<Cell> {
+        self.changes
+            .iter()
+            .find(|c| c.coord == coord)
+            .map(|c| c.after)
+    }
+
+    /// The command that reverts this one.
+    pub fn inverse(&self) -> Self {
+        Self {
+            kind: self.kind,
+            coord: self.coord,
+            changes: self
+                .changes
+                .iter()
+                .rev()
+                .map(|c| CellChange {
+                    coord: c.coord,
+                    before: c.after,
+                    after: c.before,
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether applying the command would leave the board unchanged.
+    pub fn is_noop(&self) -> bool {
+        self.changes.iter().all(|c| c.before == c.after)
+    }
+
+    /// Whether the command changes something, and every cell changed is on
+    /// the board and holds a digit and color that exist, as it must for a
+    /// command read from a file.
+    fn is_valid(&self) -> bool {
+        let on_board = |[i, j]: Coord| i < 9 && j < 9;
+        let valid = |cell: Cell| cell.value <= 9 && cell.color <= COLORS;
+        !self.changes.is_empty()
+            && on_board(self.coord)
+            && self
+                .changes
+                .iter()
+                .all(|c| on_board(c.coord) && valid(c.before) && valid(c.after))
+    }
+
+    pub fn apply(&self, sudoku: &mut Sudoku) {
+        for change in &self.changes {
+            sudoku.set_cell(change.coord, change.after);
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [i, j] = self.coord;
+        let CellChange { before, after, .. } = self.changes[0];
+        write!(f, "r{}c{}", i + 1, j + 1)?;
+        let marks = matches!(
+            self.kind,
+            CommandKind::Note | CommandKind::Eliminate | CommandKind::Color
+        );
+        if marks && self.changes.len() > 1 {
+            write!(f, " and {} more", self.changes.len() - 1)?;
+        }
+        write!(f, ": ")?;
+        // the digits set (`+`) and unset (`-`) from `b` to `a`, e.g. `+4 -7`
+        let toggled = |b: u16, a: u16| {
+            let digits: Vec<String> = (1..=9)
+                .filter(|v| (b ^ a) & (1 << v) != 0)
+                .map(|v| format!("{}{}", if a & (1 << v) != 0 { '+' } else { '-' }, v))
+                .collect();
+            digits.join(" ")
+        };
+        match self.kind {
+            CommandKind::Fill => write!(f, "{}", after.value),
+            CommandKind::Clear => write!(f, "erase {}", before.value),
+            CommandKind::Note => write!(f, "note {}", toggled(before.notes, after.notes)),
+            // removing a candidate sets its bit
+            CommandKind::Eliminate => write!(
+                f,
+                "candidate {}",
+                toggled(after.eliminated, before.eliminated)
+            ),
+            CommandKind::Hint => write!(f, "hint {}", after.value),
+            CommandKind::Color => match after.color {
+                0 => write!(f, "no color"),
+                c => write!(f, "color {}", c),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The move leading from the parent to this node (`None` for the root)
+    cmd: Option<Command>,
+    /// Index into `children` of the branch followed by `redo`
+    active: usize,
+}
+
+impl Node {
+    fn new(parent: Option<usize>, cmd: Option<Command>) -> Self {
+        Self {
+            parent,
+            children: Vec::new(),
+            cmd,
+            active: 0,
+        }
+    }
+}
+
+/// A named position in the history tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    node: usize,
+}
+
+/// The history of a game as a tree of moves.
+///
+/// All methods which change the current position return the moves that need
+/// to be applied to the board, in order, to bring it to the new position.
+///
+/// In JSON, the tree is a list of moves, each with the index of the position
+/// it was made from (`0` being the start of the game and `k` the position
+/// after the `k`th move), the branch `redo` follows from every position, the
+/// current position and the bookmarks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "TreeRepr", try_from = "TreeRepr")]
+pub struct UndoTree {
+    nodes: Vec<Node>,
+    current: usize,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Default for UndoTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UndoTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node::new(None, None)],
+            current: 0,
+            bookmarks: Vec::new(),
+        }
+    }
+
+    /// Record a move made from the current position.
+    ///
+    /// If the same move was made from here before, its branch is reused rather
+    /// than duplicated.
+    pub fn push(&mut self, cmd: Command) {
+        let current = self.current;
+        let existing = self.nodes[current]
+            .children
+            .iter()
+            .position(|&c| self.nodes[c].cmd.as_ref() == Some(&cmd));
+        let k = match existing {
+            Some(k) => k,
+            None => {
+                let id = self.nodes.len();
+                self.nodes.push(Node::new(Some(current), Some(cmd)));
+                self.nodes[current].children.push(id);
+                self.nodes[current].children.len() - 1
+            }
+        };
+        self.nodes[current].active = k;
+        self.current = self.nodes[current].children[k];
+    }
+
+    pub fn undo(&mut self) -> Option<Command> {
+        let node = &self.nodes[self.current];
+        let parent = node.parent?;
+        let cmd = node.cmd.as_ref()?.inverse();
+        // remember where we came from, so that `redo` goes back there
+        let k = self.nodes[parent]
+            .children
+            .iter()
+            .position(|&c| c == self.current)
+            .unwrap();
+        self.nodes[parent].active = k;
+        self.current = parent;
+        Some(cmd)
+    }
+
+    pub fn redo(&mut self) -> Option<Command> {
+        let node = &self.nodes[self.current];
+        let child = *node.children.get(node.active)?;
+        self.current = child;
+        self.nodes[child].cmd.clone()
+    }
+
+    /// The moves that can be redone from the current position, one per branch.
+    pub fn branches(&self) -> Vec<Command> {
+        self.nodes[self.current]
+            .children
+            .iter()
+            .filter_map(|&c| self.nodes[c].cmd.clone())
+            .collect()
+    }
+
+    /// Index of the branch `redo` will follow.
+    pub fn active_branch(&self) -> usize {
+        self.nodes[self.current].active
+    }
+
+    /// Choose the branch `redo` will follow. Out-of-range indices are ignored.
+    pub fn select_branch(&mut self, k: usize) {
+        let node = &mut self.nodes[self.current];
+        if k < node.children.len() {
+            node.active = k;
+        }
+    }
+
+    /// Number of moves between the root and the current position.
+    pub fn depth(&self) -> usize {
+        self.path_to_root(self.current).len() - 1
+    }
+
+    /// Every move recorded, on any branch
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.nodes.iter().filter_map(|n| n.cmd.as_ref())
+    }
+
+    /// Bookmark the current position.
+    pub fn bookmark(&mut self, name: String) {
+        self.bookmarks.push(Bookmark {
+            name,
+            node: self.current,
+        });
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Go to the `k`th bookmark, returning an empty list if there is no such
+    /// bookmark.
+    pub fn jump_to_bookmark(&mut self, k: usize) -> Vec<Command> {
+        match self.bookmarks.get(k) {
+            Some(b) => {
+                let node = b.node;
+                self.jump(node)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Nodes from `node` (inclusive) up to the root (inclusive).
+    fn path_to_root(&self, mut node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(parent);
+            node = parent;
+        }
+        path
+    }
+
+    fn jump(&mut self, target: usize) -> Vec<Command> {
+        let up = self.path_to_root(self.current);
+        let down = self.path_to_root(target);
+        // the paths share a common suffix, starting at the lowest common ancestor
+        let common = up
+            .iter()
+            .rev()
+            .zip(down.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut moves = Vec::new();
+        for &n in &up[..up.len() - common] {
+            moves.push(self.nodes[n].cmd.as_ref().unwrap().inverse());
+        }
+        for &n in down[..down.len() - common].iter().rev() {
+            let parent = self.nodes[n].parent.unwrap();
+            let k = self.nodes[parent]
+                .children
+                .iter()
+                .position(|&c| c == n)
+                .unwrap();
+            self.nodes[parent].active = k;
+            moves.push(self.nodes[n].cmd.clone().unwrap());
+        }
+        self.current = target;
+        moves
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Move {
+    from: usize,
+    #[serde(flatten)]
+    cmd: Command,
+}
+
+/// How an `UndoTree` is serialized
+#[derive(Serialize, Deserialize)]
+struct TreeRepr {
+    moves: Vec<Move>,
+    active: Vec<usize>,
+    current: usize,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl From<UndoTree> for TreeRepr {
+    fn from(tree: UndoTree) -> Self {
+        Self {
+            active: tree.nodes.iter().map(|n| n.active).collect(),
+            moves: tree
+                .nodes
+                .into_iter()
+                .filter_map(|n| {
+                    Some(Move {
+                        from: n.parent?,
+                        cmd: n.cmd?,
+                    })
+                })
+                .collect(),
+            current: tree.current,
+            bookmarks: tree.bookmarks,
+        }
+    }
+}
+
+impl std::convert::TryFrom<TreeRepr> for UndoTree {
+    type Error = String;
+
+    fn try_from(repr: TreeRepr) -> Result<Self, Self::Error> {
+        let mut nodes = vec![Node::new(None, None)];
+        for m in repr.moves {
+            // a move can only be made from an earlier position
+            if m.from >= nodes.len() {
+                return Err(format!(
+                    "move {} is made from a later position",
+                    nodes.len()
+                ));
+            }
+            if !m.cmd.is_valid() {
+                return Err(format!("move {} is not a valid change", nodes.len()));
+            }
+            let id = nodes.len();
+            nodes[m.from].children.push(id);
+            nodes.push(Node::new(Some(m.from), Some(m.cmd)));
+        }
+        if repr.active.len() != nodes.len() {
+            return Err("wrong number of active branches".to_owned());
+        }
+        for (node, active) in nodes.iter_mut().zip(repr.active) {
+            if active > 0 && active >= node.children.len() {
+                return Err("active branch out of range".to_owned());
+            }
+            node.active = active;
+        }
+        let len = nodes.len();
+        if repr.current >= len || repr.bookmarks.iter().any(|b| b.node >= len) {
+            return Err("position out of range".to_owned());
+        }
+        Ok(Self {
+            nodes,
+            current: repr.current,
+            bookmarks: repr.bookmarks,
+        })
+    }
+}
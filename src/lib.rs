@@ -10,5 +10,19 @@
 //! For for information, please go to [the GitHub repository](https://github.com/TianyiShi2001/sudoku-tui)
 
 pub mod board;
+pub mod cli;
+pub mod coop;
+pub mod engine;
 pub mod game;
+pub mod generator;
+pub mod history;
+pub mod linear;
+pub mod net;
+pub mod pack;
+pub mod print;
+pub mod race;
+pub mod save;
+pub mod solver;
 pub mod sudoku;
+pub mod tabs;
+pub mod versus;
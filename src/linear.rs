@@ -0,0 +1,470 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! An accessible way to play, for screen readers and braille displays.
+//!
+//! Nothing is drawn: the player types commands such as `r3c5=7`, and every
+//! change is announced as a line of plain text, e.g.
+//! `Row 3, column 5, empty, candidates 2 4 7`. Start it with
+//! `sudoku --accessible`.
+
+use crate::engine::{Action, Conflict, Event, Game};
+use crate::save;
+use crate::sudoku::{Coord, Sudoku};
+use hhmmss::Hhmmss;
+use std::io::{self, BufRead, Write};
+
+pub const HELP: &str = "\
+Commands:
+r3c5            Go to row 3, column 5
+r3c5=7          Enter 7 at row 3, column 5
+7 or =7         Enter 7 in the current cell
+0 or clear      Erase the current cell
+n7              Toggle pencil mark 7 in the current cell (r3c5n7 for another cell)
+up, down, left, right
+                Move to the next cell
+where           Read the current cell
+row, column, box
+                Read the current row, column or box; add a number for another
+                one, e.g. row 3 (boxes are numbered left to right, top to
+                bottom)
+board           Read the whole board, row by row
+auto            Switch automatic candidates on or off
+allow           Switch conflicting digits on or off; they are entered anyway
+                and read out as errors until fixed
+hint            Fill in a random cell
+undo, redo      Undo or redo the last change
+status          Time, moves and empty cells
+save [FILE]     Save the game
+help            Read this list
+quit            Stop playing";
+
+/// A game played by typing commands.
+pub struct Linear {
+    game: Game,
+    cursor: Coord,
+    quit: bool,
+}
+
+/// What a line typed by the player asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Input {
+    Go(Coord),
+    Fill(Option<Coord>, u8),
+    Note(Option<Coord>, u8),
+    Move(isize, isize),
+    Where,
+    Row(Option<usize>),
+    Column(Option<usize>),
+    Box(Option<usize>),
+    Board,
+    Auto,
+    Allow,
+    Hint,
+    Undo,
+    Redo,
+    Status,
+    Save(Option<String>),
+    Help,
+    Quit,
+}
+
+impl Linear {
+    pub fn new(game: Game) -> Self {
+        Self {
+            cursor: game.focus(),
+            game,
+            quit: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The cell being read, which may be a given
+    pub fn cursor(&self) -> Coord {
+        self.cursor
+    }
+
+    /// Whether the player asked to stop
+    pub fn is_done(&self) -> bool {
+        self.quit
+    }
+
+    /// What to say when the game starts.
+    pub fn intro(&self) -> Vec<String> {
+        vec![
+            format!(
+                "Sudoku, {}. Type help for a list of commands.",
+                count(self.empty_cells(), "empty cell")
+            ),
+            self.describe(self.cursor),
+        ]
+    }
+
+    /// Carry out a line typed by the player, returning what to announce.
+    pub fn command(&mut self, line: &str) -> Vec<String> {
+        let input = match parse(line) {
+            Ok(input) => input,
+            Err(e) => return vec![e],
+        };
+        match input {
+            Input::Go(coord) => {
+                self.go(coord);
+                vec![self.describe(coord)]
+            }
+            Input::Fill(coord, v) => self.fill(coord.unwrap_or(self.cursor), v),
+            Input::Note(coord, v) => self.note(coord.unwrap_or(self.cursor), v),
+            Input::Move(di, dj) => {
+                let [i, j] = self.cursor;
+                let (i, j) = (i as isize + di, j as isize + dj);
+                if !(0..9).contains(&i) || !(0..9).contains(&j) {
+                    return vec![format!("Edge of the board. {}", self.describe(self.cursor))];
+                }
+                self.go([i as usize, j as usize]);
+                vec![self.describe(self.cursor)]
+            }
+            Input::Where => vec![self.describe(self.cursor)],
+            Input::Row(i) => {
+                let i = i.unwrap_or(self.cursor[0]);
+                vec![self.read(format!("Row {}", i + 1), (0..9).map(|j| [i, j]))]
+            }
+            Input::Column(j) => {
+                let j = j.unwrap_or(self.cursor[1]);
+                vec![self.read(format!("Column {}", j + 1), (0..9).map(|i| [i, j]))]
+            }
+            Input::Box(b) => {
+                let [i, j] = self.cursor;
+                let b = b.unwrap_or(i / 3 * 3 + j / 3);
+                let cells = (0..9).map(|k| [b / 3 * 3 + k / 3, b % 3 * 3 + k % 3]);
+                vec![self.read(format!("Box {}", b + 1), cells)]
+            }
+            Input::Board => (0..9)
+                .map(|i| self.read(format!("Row {}", i + 1), (0..9).map(|j| [i, j])))
+                .collect(),
+            Input::Auto => {
+                let mut settings = self.game.settings();
+                settings.auto_candidates = !settings.auto_candidates;
+                self.game.set_settings(settings);
+                let state = if settings.auto_candidates {
+                    "on"
+                } else {
+                    "off"
+                };
+                vec![format!("Automatic candidates {}", state)]
+            }
+            Input::Allow => {
+                let mut settings = self.game.settings();
+                settings.allow_conflicts = !settings.allow_conflicts;
+                self.game.set_settings(settings);
+                let state = if settings.allow_conflicts {
+                    "allowed"
+                } else {
+                    "not allowed"
+                };
+                vec![format!("Conflicting digits {}", state)]
+            }
+            Input::Hint => self.act(Action::Hint, "Hint", "No empty cells left"),
+            Input::Undo => self.act(Action::Undo, "Undone", "Nothing to undo"),
+            Input::Redo => self.act(Action::Redo, "Redone", "Nothing to redo"),
+            Input::Status => vec![self.status()],
+            Input::Save(path) => {
+                let path = path.map_or_else(save::default_path, Into::into);
+                match self.game.to_saved(None).save(&path) {
+                    Ok(()) => vec![format!("Saved to {}", path.display())],
+                    Err(e) => vec![format!("Cannot save to {}: {}", path.display(), e)],
+                }
+            }
+            Input::Help => HELP.lines().map(str::to_owned).collect(),
+            Input::Quit => {
+                self.quit = true;
+                vec!["Goodbye".to_owned()]
+            }
+        }
+    }
+
+    fn go(&mut self, coord: Coord) {
+        self.cursor = coord;
+        // the game's focus never rests on givens
+        self.game.apply(Action::SetFocus(coord));
+    }
+
+    fn fill(&mut self, coord: Coord, v: u8) -> Vec<String> {
+        self.go(coord);
+        if !self.game.is_available(coord) {
+            return vec![format!("{} is given", position(coord))];
+        }
+        let action = if v == 0 {
+            Action::Clear(coord)
+        } else {
+            Action::Fill(coord, v)
+        };
+        match self.game.apply(action) {
+            Event::Changed => vec![self.describe(coord)],
+            Event::Completed => vec![self.describe(coord), self.solved()],
+            Event::Conflict(_) => {
+                let conflict = self.game.conflict().expect("the digit was rejected");
+                let cells: Vec<String> = conflict
+                    .with
+                    .iter()
+                    .map(|&c| position(c).to_lowercase())
+                    .collect();
+                let units: Vec<String> = conflict.units().iter().map(|u| u.to_string()).collect();
+                vec![format!(
+                    "Cannot enter {} at {}: there is already a {} at {}, in {}",
+                    v,
+                    position(coord).to_lowercase(),
+                    v,
+                    cells.join(" and at "),
+                    units.join(" and ")
+                )]
+            }
+            Event::Ignored => vec![format!("No change. {}", self.describe(coord))],
+        }
+    }
+
+    fn note(&mut self, coord: Coord, v: u8) -> Vec<String> {
+        self.go(coord);
+        match self.game.apply(Action::ToggleNote(coord, v)) {
+            Event::Ignored if !(1..=9).contains(&v) => vec!["Pencil marks are 1 to 9".to_owned()],
+            Event::Ignored if self.game.sudoku()[coord] != 0 => {
+                vec![format!("{} is not empty", position(coord))]
+            }
+            Event::Ignored => vec![format!("{} is not a candidate", v)],
+            _ => vec![self.describe(coord)],
+        }
+    }
+
+    /// Apply `action`, announcing the cells it changed after `done`.
+    fn act(&mut self, action: Action, done: &str, ignored: &str) -> Vec<String> {
+        let before = self.game.sudoku().clone();
+        let event = self.game.apply(action);
+        if event == Event::Ignored {
+            return vec![ignored.to_owned()];
+        }
+        let changed = changed_cells(&before, self.game.sudoku());
+        if let Some(&coord) = changed.first() {
+            self.go(coord);
+        }
+        let mut lines: Vec<String> = changed
+            .iter()
+            .map(|&c| format!("{}. {}", done, self.describe(c)))
+            .collect();
+        if lines.is_empty() {
+            lines.push(done.to_owned());
+        }
+        if event == Event::Completed {
+            lines.push(self.solved());
+        }
+        lines
+    }
+
+    /// `Row 3, column 5, empty, candidates 2 4 7`
+    pub fn describe(&self, coord: Coord) -> String {
+        let cell = self.game.cell(coord);
+        let mut text = position(coord);
+        if !self.game.is_available(coord) {
+            text += &format!(", {}, given", cell.value);
+        } else if cell.value != 0 {
+            text += &format!(", {}", cell.value);
+            let with = self.game.sudoku().conflicts(cell.value, coord);
+            if !with.is_empty() {
+                let conflict = Conflict {
+                    coord,
+                    value: cell.value,
+                    with,
+                };
+                let units: Vec<String> = conflict.units().iter().map(|u| u.to_string()).collect();
+                text += &format!(", error in {}", units.join(" and "));
+            }
+        } else {
+            text += ", empty";
+            if self.game.settings().auto_candidates {
+                match self.game.candidates(coord) {
+                    0 => text += ", no candidates",
+                    mask => text += &format!(", candidates {}", digits(mask)),
+                }
+            } else if cell.notes != 0 {
+                text += &format!(", pencil marks {}", digits(cell.notes));
+            }
+        }
+        if cell.color != 0 {
+            text += &format!(", color {}", cell.color);
+        }
+        text
+    }
+
+    /// `Row 3: 5, 3, empty, ...`
+    fn read(&self, name: String, cells: impl Iterator<Item = Coord>) -> String {
+        let sudoku = self.game.sudoku();
+        let values: Vec<String> = cells
+            .map(|c| match sudoku[c] {
+                0 => "empty".to_owned(),
+                v => v.to_string(),
+            })
+            .collect();
+        format!("{}: {}", name, values.join(", "))
+    }
+
+    fn empty_cells(&self) -> usize {
+        (0..81)
+            .filter(|&x| self.game.sudoku()[[x / 9, x % 9]] == 0)
+            .count()
+    }
+
+    fn status(&self) -> String {
+        format!(
+            "Time {}, {}, {}, {}",
+            self.game.elapsed().hhmmss(),
+            count(self.game.moves(), "move"),
+            count(self.game.hints(), "hint"),
+            count(self.empty_cells(), "empty cell")
+        )
+    }
+
+    fn solved(&self) -> String {
+        format!(
+            "Solved! Time {}, {}, {}",
+            self.game.elapsed().hhmmss(),
+            count(self.game.moves(), "move"),
+            count(self.game.hints(), "hint")
+        )
+    }
+}
+
+/// Play in the terminal, reading commands from `input` and writing
+/// announcements to `output`, one per line.
+pub fn run(game: Game, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut linear = Linear::new(game);
+    for line in linear.intro() {
+        writeln!(output, "{}", line)?;
+    }
+    let mut lines = input.lines();
+    while !linear.is_done() {
+        write!(output, "> ")?;
+        output.flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        for text in linear.command(&line) {
+            writeln!(output, "{}", text)?;
+        }
+    }
+    Ok(())
+}
+
+/// `Row 3, column 5`
+fn position([i, j]: Coord) -> String {
+    format!("Row {}, column {}", i + 1, j + 1)
+}
+
+/// `1 hint`, `2 hints`
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn digits(mask: u16) -> String {
+    (1..=9)
+        .filter(|v| mask & (1 << v) != 0)
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The cells whose digit changed or, if there are none, those whose pencil
+/// marks or candidates changed
+fn changed_cells(before: &Sudoku, after: &Sudoku) -> Vec<Coord> {
+    let cells = || (0..81).map(|x| [x / 9, x % 9]);
+    let digits: Vec<Coord> = cells().filter(|&c| before[c] != after[c]).collect();
+    if !digits.is_empty() {
+        return digits;
+    }
+    cells()
+        .filter(|&c| before.cell(c) != after.cell(c))
+        .collect()
+}
+
+fn parse(line: &str) -> Result<Input, String> {
+    let line = line.trim();
+    let (word, rest) = match line.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (line, ""),
+    };
+    let word = word.to_ascii_lowercase();
+    // "row 3" and "row3" are the same
+    let (name, number) = word.split_at(
+        word.find(|c: char| c.is_ascii_digit())
+            .unwrap_or(word.len()),
+    );
+    let number = if number.is_empty() { rest } else { number };
+    let unit = || -> Result<Option<usize>, String> {
+        match number {
+            "" => Ok(None),
+            n => match n.parse() {
+                Ok(n @ 1..=9) => Ok(Some(n - 1)),
+                _ => Err(format!("There is no {} {}", name, n)),
+            },
+        }
+    };
+    let input = match name {
+        "" if line.is_empty() => Input::Where,
+        "where" | "." => Input::Where,
+        "up" => Input::Move(-1, 0),
+        "down" => Input::Move(1, 0),
+        "left" => Input::Move(0, -1),
+        "right" => Input::Move(0, 1),
+        "row" => Input::Row(unit()?),
+        "col" | "column" => Input::Column(unit()?),
+        "box" => Input::Box(unit()?),
+        "board" | "read" => Input::Board,
+        "auto" => Input::Auto,
+        "allow" => Input::Allow,
+        "hint" => Input::Hint,
+        "undo" => Input::Undo,
+        "redo" => Input::Redo,
+        "status" => Input::Status,
+        "clear" | "erase" => Input::Fill(None, 0),
+        "save" => Input::Save(Some(rest.to_owned()).filter(|p| !p.is_empty())),
+        "help" | "?" => Input::Help,
+        "quit" | "exit" | "q" => Input::Quit,
+        _ => parse_cell(&line.to_ascii_lowercase().replace(char::is_whitespace, "")).ok_or_else(
+            || {
+                format!(
+                    "Unknown command '{}'. Type help for a list of commands.",
+                    line
+                )
+            },
+        )?,
+    };
+    Ok(input)
+}
+
+/// `r3c5`, `r3c5=7`, `r3c5n7`, `=7`, `7` or `n7`
+fn parse_cell(s: &str) -> Option<Input> {
+    let digit = |c: u8| (c as char).to_digit(10).map(|d| d as u8);
+    let b = s.as_bytes();
+    let (coord, rest) = match b {
+        [b'r', i, b'c', j, rest @ ..] => {
+            let (i, j) = (digit(*i)?, digit(*j)?);
+            if !(1..=9).contains(&i) || !(1..=9).contains(&j) {
+                return None;
+            }
+            (Some([i as usize - 1, j as usize - 1]), rest)
+        }
+        _ => (None, b),
+    };
+    match (coord, rest) {
+        (Some(coord), []) => Some(Input::Go(coord)),
+        (_, [b'=', v]) | (_, [v]) => Some(Input::Fill(coord, digit(*v)?)),
+        (_, [b'n', v]) => Some(Input::Note(coord, digit(*v)?)),
+        _ => None,
+    }
+}
@@ -3,8 +3,12 @@
 // This software is released under the MIT License.
 // https://opensource.org/licenses/MIT
 
-use sudoku_tui::game::run;
+use sudoku_tui::{cli, game::run};
 
 fn main() {
-    run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&args) {
+        Some(code) => std::process::exit(code),
+        None => run(),
+    }
 }
@@ -0,0 +1,59 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Messages between game instances over TCP, one JSON object per line.
+//!
+//! Used by the multiplayer modes, e.g. [`race`](crate::race).
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, BufRead, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+/// Port the multiplayer modes use unless told otherwise
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Write `message` as a line of JSON.
+pub fn send<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Read the next message, or `None` once the other side has hung up.
+pub fn receive<T: DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Send messages to `stream` from a thread of its own, so that a slow or
+/// stalled peer does not hold up whoever sends them. The thread stops once the
+/// stream fails or the sender is dropped; sending fails after that.
+pub fn outbox<T: Serialize + Send + 'static>(mut stream: TcpStream) -> Sender<T> {
+    let (sender, messages) = mpsc::channel();
+    thread::spawn(move || {
+        for message in messages {
+            if send(&mut stream, &message).is_err() {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+/// Lock `mutex`, even if a thread panicked while holding it: the state shared
+/// between connections stays usable, and one bad connection does not bring
+/// down the others.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,227 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Puzzle collections ("packs") and the player's progress through them.
+//!
+//! A pack is a text file with one puzzle per line: 81 characters (`0` or `.`
+//! for empty cells), optionally followed by a rating and a name, e.g.
+//!
+//! ```text
+//! # lines starting with '#' are comments
+//! 53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79 1.2 Classic
+//! ..9748...7.........2.1.9.....7...24..64.1.59..98...3.....8.3.2.........6...2759.. Swordfish
+//! ```
+//!
+//! This means the output of `sudoku rate` is itself a valid pack.
+
+use crate::sudoku::{ParseSudokuError, Sudoku};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct PackEntry {
+    pub puzzle: Sudoku,
+    pub name: Option<String>,
+    pub rating: Option<f32>,
+}
+
+impl PackEntry {
+    /// Read a line of a pack file, returning `None` for blank lines and
+    /// comments.
+    pub fn parse(line: &str) -> Option<Result<Self, ParseSudokuError>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (puzzle, rest) = match line.find(char::is_whitespace) {
+            Some(k) => (&line[..k], line[k..].trim()),
+            None => (line, ""),
+        };
+        let puzzle = match puzzle.parse() {
+            Ok(puzzle) => puzzle,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut words = rest.splitn(2, char::is_whitespace);
+        let (rating, rest) = match words.next().unwrap_or_default().parse() {
+            Ok(rating) => (Some(rating), words.next().unwrap_or_default().trim()),
+            Err(_) => (None, rest),
+        };
+        let name = if rest.is_empty() {
+            None
+        } else {
+            Some(rest.to_owned())
+        };
+        Some(Ok(Self {
+            puzzle,
+            name,
+            rating,
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(io::Error),
+    /// A malformed puzzle on the given (1-based) line
+    Parse(usize, ParseSudokuError),
+    Empty,
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse(line, e) => write!(f, "line {}: {}", line, e),
+            Self::Empty => write!(f, "no puzzles found"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<io::Error> for PackError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub name: String,
+    pub entries: Vec<PackEntry>,
+}
+
+impl Pack {
+    pub fn parse(name: &str, text: &str) -> Result<Self, PackError> {
+        let mut entries = Vec::new();
+        for (k, line) in text.lines().enumerate() {
+            match PackEntry::parse(line) {
+                Some(Ok(entry)) => entries.push(entry),
+                Some(Err(e)) => return Err(PackError::Parse(k + 1, e)),
+                None => {}
+            }
+        }
+        if entries.is_empty() {
+            return Err(PackError::Empty);
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            entries,
+        })
+    }
+
+    /// Load a pack file, named after the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PackError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::parse(&name, &fs::read_to_string(path)?)
+    }
+
+    /// Display name of the `k`th puzzle
+    pub fn title(&self, k: usize) -> String {
+        match &self.entries[k].name {
+            Some(name) => format!("{} #{}: {}", self.name, k + 1, name),
+            None => format!("{} #{}", self.name, k + 1),
+        }
+    }
+}
+
+/// Where the game keeps its files, in the user's data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/share")))?;
+    Some(dir.join("sudoku-tui"))
+}
+
+/// Best times of all puzzles the player has completed, keyed by the puzzle so
+/// that they are shared between packs.
+///
+/// Saved as lines of `<puzzle> <seconds>`.
+#[derive(Debug, Default)]
+pub struct Progress {
+    path: Option<PathBuf>,
+    best: HashMap<String, Duration>,
+}
+
+impl Progress {
+    /// Progress kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the progress file in the user's data directory, starting afresh
+    /// if it does not exist yet.
+    pub fn load() -> Self {
+        match Self::default_path() {
+            Some(path) => Self::load_from(path),
+            None => Self::new(),
+        }
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let mut best = HashMap::new();
+        if let Ok(text) = fs::read_to_string(&path) {
+            for line in text.lines() {
+                let mut parts = line.split_whitespace();
+                if let (Some(puzzle), Some(Ok(secs))) = (parts.next(), parts.next().map(str::parse))
+                {
+                    best.insert(puzzle.to_owned(), Duration::from_secs(secs));
+                }
+            }
+        }
+        Self {
+            path: Some(path),
+            best,
+        }
+    }
+
+    fn default_path() -> Option<PathBuf> {
+        Some(data_dir()?.join("progress.txt"))
+    }
+
+    pub fn best(&self, puzzle: &Sudoku) -> Option<Duration> {
+        self.best.get(&puzzle.to_line()).copied()
+    }
+
+    pub fn completed(&self, puzzle: &Sudoku) -> bool {
+        self.best(puzzle).is_some()
+    }
+
+    /// Record a completion, keeping the better time, and save to disk.
+    pub fn record(&mut self, puzzle: &Sudoku, time: Duration) -> io::Result<()> {
+        let time = Duration::from_secs(time.as_secs());
+        let best = self.best.entry(puzzle.to_line()).or_insert(time);
+        if time < *best {
+            *best = time;
+        }
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut lines: Vec<String> = self
+            .best
+            .iter()
+            .map(|(puzzle, time)| format!("{} {}", puzzle, time.as_secs()))
+            .collect();
+        lines.sort();
+        fs::write(path, lines.join("\n") + "\n")
+    }
+}
@@ -0,0 +1,559 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Printable puzzle sheets, as PDF or PostScript.
+//!
+//! A sheet lays out one to six puzzles per page, each with a title and its
+//! difficulty, optionally followed by pages with the solutions:
+//!
+//! ```
+//! use sudoku_tui::generator::Generator;
+//! use sudoku_tui::print::{Format, Item, Sheet};
+//!
+//! let puzzle = Generator::new().seed(1).generate();
+//! let sheet = Sheet::new("Sunday")
+//!     .per_page(4)
+//!     .solutions(true)
+//!     .item(Item::new("Puzzle 1", puzzle));
+//! let pdf = sheet.render(Format::Pdf);
+//! assert!(pdf.starts_with(b"%PDF"));
+//! ```
+//!
+//! Only the standard Helvetica fonts are used, so text is limited to ASCII.
+
+use crate::generator::{Difficulty, ParseOptionError, Puzzle, Variant};
+use crate::solver::rate;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Pdf,
+    PostScript,
+}
+
+impl Format {
+    /// The format suggested by the extension of `path`, if any.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        ext.parse().ok()
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pdf => write!(f, "pdf"),
+            Self::PostScript => write!(f, "ps"),
+        }
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pdf" => Ok(Self::Pdf),
+            "ps" | "postscript" => Ok(Self::PostScript),
+            _ => Err(ParseOptionError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Paper {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl Paper {
+    /// Width and height in points
+    fn size(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.0, 842.0),
+            Self::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+impl std::str::FromStr for Paper {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "a4" => Ok(Self::A4),
+            "letter" => Ok(Self::Letter),
+            _ => Err(ParseOptionError(s.to_owned())),
+        }
+    }
+}
+
+/// A puzzle on a sheet
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub title: String,
+    /// Printed under the title, e.g. the difficulty
+    pub subtitle: String,
+    pub puzzle: Puzzle,
+}
+
+impl Item {
+    /// A puzzle with its difficulty (or, for other variants, its size and
+    /// variant) as the subtitle.
+    pub fn new(title: impl Into<String>, puzzle: Puzzle) -> Self {
+        let subtitle = match puzzle.sudoku() {
+            Some(sudoku) => {
+                let rating = rate(&sudoku);
+                let difficulty = Difficulty::ALL
+                    .iter()
+                    .find(|d| d.contains(&rating))
+                    .unwrap_or(&Difficulty::Expert);
+                if rating.solved {
+                    format!("Difficulty: {} ({:.1})", difficulty, rating.rating)
+                } else {
+                    format!("Difficulty: {}", difficulty)
+                }
+            }
+            None => format!(
+                "{} {}, {} clues",
+                puzzle.shape,
+                puzzle.variant,
+                puzzle.clues()
+            ),
+        };
+        Self {
+            title: title.into(),
+            subtitle,
+            puzzle,
+        }
+    }
+}
+
+/// Puzzles laid out on pages.
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    title: String,
+    subtitle: Option<String>,
+    items: Vec<Item>,
+    per_page: usize,
+    solutions: bool,
+    paper: Paper,
+}
+
+impl Sheet {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            items: Vec::new(),
+            per_page: 1,
+            solutions: false,
+            paper: Paper::A4,
+        }
+    }
+
+    /// Printed under the title of every page, e.g. the seed of the puzzles.
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Puzzles on each page, from 1 to 6.
+    pub fn per_page(mut self, n: usize) -> Self {
+        self.per_page = n.clamp(1, 6);
+        self
+    }
+
+    /// Add pages with the solutions after the puzzles.
+    pub fn solutions(mut self, solutions: bool) -> Self {
+        self.solutions = solutions;
+        self
+    }
+
+    pub fn paper(mut self, paper: Paper) -> Self {
+        self.paper = paper;
+        self
+    }
+
+    pub fn item(mut self, item: Item) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn items(mut self, items: impl IntoIterator<Item = Item>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// Number of pages, including solutions
+    pub fn pages(&self) -> usize {
+        let pages = self.items.len().div_ceil(self.per_page).max(1);
+        if self.solutions && !self.items.is_empty() {
+            pages * 2
+        } else {
+            pages
+        }
+    }
+
+    pub fn render(&self, format: Format) -> Vec<u8> {
+        let pages = self.layout();
+        match format {
+            Format::Pdf => self.to_pdf(&pages),
+            Format::PostScript => self.to_postscript(&pages).into_bytes(),
+        }
+    }
+
+    /// Render the sheet to `path`, in the format given by its extension
+    /// (PDF if it has none).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        fs::write(
+            path,
+            self.render(Format::from_path(path).unwrap_or_default()),
+        )
+    }
+
+    fn layout(&self) -> Vec<Vec<Op>> {
+        let chunks: Vec<&[Item]> = self.items.chunks(self.per_page).collect();
+        let mut pages: Vec<Vec<Op>> = if chunks.is_empty() {
+            vec![self.page(&[], false)]
+        } else {
+            chunks.iter().map(|items| self.page(items, false)).collect()
+        };
+        if self.solutions {
+            pages.extend(chunks.iter().map(|items| self.page(items, true)));
+        }
+        let n = pages.len();
+        for (k, page) in pages.iter_mut().enumerate() {
+            let (_, height) = self.paper.size();
+            page.push(Op::text(
+                MARGIN,
+                height - MARGIN + 10.0,
+                9.0,
+                false,
+                format!("Page {} of {}", k + 1, n),
+            ));
+        }
+        pages
+    }
+
+    fn page(&self, items: &[Item], solutions: bool) -> Vec<Op> {
+        let (width, height) = self.paper.size();
+        let mut ops = Vec::new();
+        let title = if solutions {
+            format!("{} - Solutions", self.title)
+        } else {
+            self.title.clone()
+        };
+        ops.push(Op::text(MARGIN, MARGIN + 16.0, 16.0, true, title));
+        if let Some(subtitle) = &self.subtitle {
+            ops.push(Op::text(
+                MARGIN,
+                MARGIN + 32.0,
+                10.0,
+                false,
+                subtitle.clone(),
+            ));
+        }
+
+        let (cols, rows) = match self.per_page {
+            1 => (1, 1),
+            2 => (1, 2),
+            3 | 4 => (2, 2),
+            _ => (2, 3),
+        };
+        let top = MARGIN + 48.0;
+        let slot_w = (width - 2.0 * MARGIN) / cols as f32;
+        let slot_h = (height - MARGIN - 10.0 - top) / rows as f32;
+        let grid = (slot_w - 24.0).min(slot_h - CAPTION - 24.0);
+        for (k, item) in items.iter().enumerate() {
+            let x = MARGIN + (k % cols) as f32 * slot_w + (slot_w - grid) / 2.0;
+            let y = top + (k / cols) as f32 * slot_h + (slot_h - CAPTION - grid) / 2.0;
+            let title = if solutions {
+                format!("Solution: {}", item.title)
+            } else {
+                item.title.clone()
+            };
+            ops.push(Op::text(x, y + 12.0, 12.0, true, title));
+            ops.push(Op::text(x, y + 25.0, 9.0, false, item.subtitle.clone()));
+            draw_puzzle(&mut ops, &item.puzzle, x, y + CAPTION, grid, solutions);
+        }
+        ops
+    }
+
+    fn to_pdf(&self, pages: &[Vec<Op>]) -> Vec<u8> {
+        let (width, height) = self.paper.size();
+        // 1: catalog, 2: page tree, 3-4: fonts, 5: info, then a page and its
+        // contents for each page
+        let page_id = |k: usize| 6 + 2 * k;
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..pages.len())
+                    .map(|k| format!("{} 0 R", page_id(k)))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                pages.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_owned(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_owned(),
+            format!(
+                "<< /Title ({}) /Producer (sudoku-tui) >>",
+                escape(&self.title)
+            ),
+        ];
+        for (k, ops) in pages.iter().enumerate() {
+            let mut content = String::new();
+            for op in ops {
+                op.write_pdf(&mut content, height);
+            }
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                width,
+                height,
+                page_id(k) + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (k, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = write!(out, "{} 0 obj\n{}\nendobj\n", k + 1, object);
+        }
+        let xref = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(out, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        out.into_bytes()
+    }
+
+    fn to_postscript(&self, pages: &[Vec<Op>]) -> String {
+        let (width, height) = self.paper.size();
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "%!PS-Adobe-3.0\n\
+             %%Title: {}\n\
+             %%Creator: sudoku-tui\n\
+             %%BoundingBox: 0 0 {} {}\n\
+             %%Pages: {}\n\
+             %%EndComments\n\
+             %%BeginProlog\n\
+             /L {{ setgray setlinewidth newpath moveto lineto stroke }} def\n\
+             /T {{ 0 setgray findfont exch scalefont setfont moveto show }} def\n\
+             %%EndProlog\n",
+            ascii(&self.title),
+            width,
+            height,
+            pages.len()
+        );
+        for (k, ops) in pages.iter().enumerate() {
+            let _ = writeln!(out, "%%Page: {} {}", k + 1, k + 1);
+            let _ = writeln!(out, "<< /PageSize [{} {}] >> setpagedevice", width, height);
+            for op in ops {
+                op.write_postscript(&mut out, height);
+            }
+            out.push_str("showpage\n");
+        }
+        out.push_str("%%EOF\n");
+        out
+    }
+}
+
+const MARGIN: f32 = 40.0;
+/// Height of an item's title and subtitle
+const CAPTION: f32 = 32.0;
+
+/// Draw the grid of `puzzle` with its top left corner at `(x, y)`.
+fn draw_puzzle(ops: &mut Vec<Op>, puzzle: &Puzzle, x: f32, y: f32, size: f32, solution: bool) {
+    let n = puzzle.shape.size();
+    let (h, w) = (puzzle.shape.box_height, puzzle.shape.box_width);
+    let cell = size / n as f32;
+    if puzzle.variant == Variant::Diagonal {
+        ops.push(Op::line((x, y), (x + size, y + size), 0.5, 0.7));
+        ops.push(Op::line((x + size, y), (x, y + size), 0.5, 0.7));
+    }
+    for k in 0..=n {
+        let d = k as f32 * cell;
+        let thick = |boxes: usize| if k % boxes == 0 { 2.0 } else { 0.5 };
+        ops.push(Op::line((x, y + d), (x + size, y + d), thick(h), 0.0));
+        ops.push(Op::line((x + d, y), (x + d, y + size), thick(w), 0.0));
+    }
+    let font = cell * 0.6;
+    for (k, (&given, &answer)) in puzzle.givens.iter().zip(&puzzle.solution).enumerate() {
+        let v = match (given, solution) {
+            (0, false) => continue,
+            (0, true) => answer,
+            (v, _) => v,
+        };
+        // Helvetica digits are 0.556 em wide and about 0.7 em tall
+        let cx = x + (k % n) as f32 * cell + cell / 2.0;
+        let cy = y + (k / n) as f32 * cell + cell / 2.0;
+        ops.push(Op::text(
+            cx - font * 0.278,
+            cy + font * 0.35,
+            font,
+            given != 0,
+            v.to_string(),
+        ));
+    }
+}
+
+/// Something drawn on a page, in points from the top left corner.
+#[derive(Debug, Clone)]
+enum Op {
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        gray: f32,
+    },
+    /// Text starting at `(x, y)` on the baseline
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        bold: bool,
+        text: String,
+    },
+}
+
+impl Op {
+    fn line(from: (f32, f32), to: (f32, f32), width: f32, gray: f32) -> Self {
+        Self::Line {
+            from,
+            to,
+            width,
+            gray,
+        }
+    }
+
+    fn text(x: f32, y: f32, size: f32, bold: bool, text: String) -> Self {
+        Self::Text {
+            x,
+            y,
+            size,
+            bold,
+            text,
+        }
+    }
+
+    fn write_pdf(&self, out: &mut String, height: f32) {
+        let _ = match self {
+            Self::Line {
+                from,
+                to,
+                width,
+                gray,
+            } => writeln!(
+                out,
+                "{:.2} w {:.2} G {:.2} {:.2} m {:.2} {:.2} l S",
+                width,
+                gray,
+                from.0,
+                height - from.1,
+                to.0,
+                height - to.1
+            ),
+            Self::Text {
+                x,
+                y,
+                size,
+                bold,
+                text,
+            } => writeln!(
+                out,
+                "BT /F{} {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+                if *bold { 2 } else { 1 },
+                size,
+                x,
+                height - y,
+                escape(text)
+            ),
+        };
+    }
+
+    fn write_postscript(&self, out: &mut String, height: f32) {
+        let _ = match self {
+            Self::Line {
+                from,
+                to,
+                width,
+                gray,
+            } => writeln!(
+                out,
+                "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} L",
+                to.0,
+                height - to.1,
+                from.0,
+                height - from.1,
+                width,
+                gray
+            ),
+            Self::Text {
+                x,
+                y,
+                size,
+                bold,
+                text,
+            } => writeln!(
+                out,
+                "({}) {:.2} {:.2} {:.2} /Helvetica{} T",
+                escape(text),
+                x,
+                height - y,
+                size,
+                if *bold { "-Bold" } else { "" }
+            ),
+        };
+    }
+}
+
+/// `s` with anything the standard fonts cannot show replaced by `?`
+fn ascii(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c == ' ' || c.is_ascii_graphic() {
+                c
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+/// `s` as the contents of a PDF or PostScript string literal
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in ascii(s).chars() {
+        if matches!(c, '(' | ')' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
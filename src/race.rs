@@ -0,0 +1,331 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Racing other players on the same puzzle over TCP.
+//!
+//! One instance runs a [`Host`], which generates a puzzle from a seed and
+//! keeps the standings. Every player, including the one hosting, joins it as
+//! a [`Racer`]: the host sends the puzzle, racers report how many cells they
+//! have filled, their mistakes and their finish time, and the host sends the
+//! standings of everyone back whenever they change.
+//!
+//! Messages are the JSON objects of [`net`](crate::net), e.g.
+//! `{"type":"progress","filled":12,"mistakes":1}`.
+//!
+//! ```no_run
+//! use sudoku_tui::race::{Host, Racer};
+//!
+//! let host = Host::bind("0.0.0.0:7878", 42)?;
+//! let racer = Racer::join("127.0.0.1:7878", "Alice")?;
+//! assert_eq!(racer.seed(), host.seed());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::engine::Game;
+use crate::generator::{Generator, Puzzle};
+use crate::net::{lock, outbox, receive, send};
+use crate::sudoku::Sudoku;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How far a player has got
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    /// Cells the player has put a digit in, right or wrong
+    pub filled: usize,
+    /// Digits entered that do not agree with the solution
+    pub mistakes: usize,
+    /// Time taken to solve the puzzle, in milliseconds
+    pub finished_ms: Option<u64>,
+    /// Whether the player has disconnected
+    pub left: bool,
+}
+
+impl Standing {
+    pub fn finish_time(&self) -> Option<Duration> {
+        self.finished_ms.map(Duration::from_millis)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Join { name: String },
+    Progress { filled: usize, mistakes: usize },
+    Finished { elapsed_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HostMessage {
+    Welcome {
+        id: usize,
+        seed: u64,
+        puzzle: String,
+    },
+    Standings {
+        players: Vec<Standing>,
+    },
+}
+
+/// What the host knows about the race
+struct Room {
+    seed: u64,
+    puzzle: String,
+    players: Vec<Standing>,
+    /// Where to send the standings, by player; `None` once they left
+    writers: Vec<Option<Sender<HostMessage>>>,
+}
+
+impl Room {
+    /// Queue the standings for every player; the lock on the room is not held
+    /// while they are written.
+    fn broadcast(&mut self) {
+        let message = HostMessage::Standings {
+            players: self.players.clone(),
+        };
+        for writer in &mut self.writers {
+            if let Some(sender) = writer {
+                if sender.send(message.clone()).is_err() {
+                    *writer = None;
+                }
+            }
+        }
+    }
+}
+
+/// Serves a race in the background until the program exits.
+#[derive(Debug)]
+pub struct Host {
+    addr: SocketAddr,
+    seed: u64,
+    puzzle: Puzzle,
+}
+
+impl Host {
+    /// Listen on `addr` (port 0 picks a free one) and race on the puzzle
+    /// generated from `seed`.
+    pub fn bind(addr: impl ToSocketAddrs, seed: u64) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let puzzle = Generator::new().seed(seed).generate();
+        let room = Arc::new(Mutex::new(Room {
+            seed,
+            puzzle: puzzle.to_line(),
+            players: Vec::new(),
+            writers: Vec::new(),
+        }));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let room = Arc::clone(&room);
+                thread::spawn(move || serve(stream, room));
+            }
+        });
+        Ok(Self { addr, seed, puzzle })
+    }
+
+    /// The address players join, e.g. with the port picked by the system
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+}
+
+/// Talk to one player until they hang up.
+fn serve(stream: TcpStream, room: Arc<Mutex<Room>>) {
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+    let name = match receive(&mut reader) {
+        Ok(Some(ClientMessage::Join { name })) => name,
+        _ => return,
+    };
+    let writer = outbox(stream);
+    let id = {
+        let mut room = lock(&room);
+        let id = room.players.len();
+        let welcome = HostMessage::Welcome {
+            id,
+            seed: room.seed,
+            puzzle: room.puzzle.clone(),
+        };
+        if writer.send(welcome).is_err() {
+            return;
+        }
+        room.players.push(Standing {
+            name,
+            ..Standing::default()
+        });
+        room.writers.push(Some(writer));
+        room.broadcast();
+        id
+    };
+    while let Ok(Some(message)) = receive(&mut reader) {
+        let mut room = lock(&room);
+        let player = &mut room.players[id];
+        match message {
+            ClientMessage::Progress { filled, mistakes } => {
+                player.filled = filled;
+                player.mistakes = mistakes;
+            }
+            ClientMessage::Finished { elapsed_ms } => player.finished_ms = Some(elapsed_ms),
+            ClientMessage::Join { .. } => continue,
+        }
+        room.broadcast();
+    }
+    let mut room = lock(&room);
+    room.players[id].left = true;
+    room.writers[id] = None;
+    room.broadcast();
+}
+
+/// A player taking part in a race.
+#[derive(Debug)]
+pub struct Racer {
+    id: usize,
+    seed: u64,
+    puzzle: Sudoku,
+    writer: TcpStream,
+    standings: Arc<Mutex<Vec<Standing>>>,
+    connected: Arc<AtomicBool>,
+    mistakes: usize,
+    /// The progress last reported
+    reported: (usize, usize),
+}
+
+impl Racer {
+    /// Join the race hosted at `addr` under `name`, and receive the puzzle.
+    pub fn join(addr: impl ToSocketAddrs, name: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let name = name.to_owned();
+        send(&mut writer, &ClientMessage::Join { name })?;
+        let (id, seed, puzzle) = match receive(&mut reader)? {
+            Some(HostMessage::Welcome { id, seed, puzzle }) => (id, seed, puzzle),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the host did not send a puzzle",
+                ))
+            }
+        };
+        let puzzle = puzzle
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let standings = Arc::new(Mutex::new(Vec::new()));
+        let connected = Arc::new(AtomicBool::new(true));
+        let (shared, flag) = (Arc::clone(&standings), Arc::clone(&connected));
+        thread::spawn(move || {
+            while let Ok(Some(message)) = receive(&mut reader) {
+                if let HostMessage::Standings { players } = message {
+                    *lock(&shared) = players;
+                }
+            }
+            flag.store(false, Ordering::Relaxed);
+        });
+        Ok(Self {
+            id,
+            seed,
+            puzzle,
+            writer,
+            standings,
+            connected,
+            mistakes: 0,
+            reported: (0, 0),
+        })
+    }
+
+    /// The position of the player in [`Racer::standings`]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The puzzle everyone races on
+    pub fn puzzle(&self) -> &Sudoku {
+        &self.puzzle
+    }
+
+    /// Cells to fill in the puzzle
+    pub fn cells(&self) -> usize {
+        81 - self
+            .puzzle
+            .matrix()
+            .iter()
+            .flatten()
+            .filter(|&&v| v != 0)
+            .count()
+    }
+
+    /// Count a digit that does not agree with the solution.
+    pub fn mistake(&mut self) {
+        self.mistakes += 1;
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    /// Tell the host how far `game` has got, if that changed.
+    pub fn update(&mut self, game: &Game) -> io::Result<()> {
+        let filled = (0..81)
+            .map(|x| [x / 9, x % 9])
+            .filter(|&c| game.is_available(c) && game.sudoku()[c] != 0)
+            .count();
+        if (filled, self.mistakes) == self.reported {
+            return Ok(());
+        }
+        self.reported = (filled, self.mistakes);
+        let progress = ClientMessage::Progress {
+            filled,
+            mistakes: self.mistakes,
+        };
+        send(&mut self.writer, &progress)
+    }
+
+    /// Tell the host the puzzle was solved in `elapsed`.
+    pub fn finish(&mut self, elapsed: Duration) -> io::Result<()> {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        send(&mut self.writer, &ClientMessage::Finished { elapsed_ms })
+    }
+
+    /// Everyone's progress, as last heard from the host
+    pub fn standings(&self) -> Vec<Standing> {
+        lock(&self.standings).clone()
+    }
+
+    /// Whether the host can still be heard from
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Racer {
+    /// Leave the race. The thread reading the standings holds a handle to the
+    /// connection too, so it has to be shut down explicitly.
+    fn drop(&mut self) {
+        let _ = self.writer.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Saved games.
+//!
+//! A game is saved as a JSON document holding the puzzle, the board as the
+//! player left it (entries, notes, eliminations and colors), the whole undo
+//! tree, the time spent so far and some metadata:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "metadata": { "name": null, "symmetry": "rotational", "rating": 2.3, "hardest": "Naked Single" },
+//!   "variant": "classic",
+//!   "puzzle": "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+//!   "solution": "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+//!   "board": { "givens": "...", "entries": "...", "notes": [0, ...], "eliminated": [0, ...] },
+//!   "focus": [0, 2],
+//!   "history": { "moves": [...], "active": [...], "current": 0, "bookmarks": [] },
+//!   "elapsed_ms": 83000,
+//!   "moves": 12, "undos": 1, "hints": 0,
+//!   "settings": { "auto_candidates": false, "show_candidates": true, "keep_eliminations": false }
+//! }
+//! ```
+//!
+//! The format is versioned: documents written by older versions are migrated
+//! when they are read. Version 0 is a puzzle as written by
+//! `sudoku generate --json`, which is loaded as a new game; of a whole batch
+//! of puzzles, the first is loaded.
+
+use crate::engine::Settings;
+use crate::generator::{Symmetry, Variant};
+use crate::history::UndoTree;
+use crate::sudoku::{Coord, Sudoku};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The version written by this version of the game
+pub const VERSION: u64 = 1;
+
+/// Information about the puzzle, for the player and other tools
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub symmetry: Symmetry,
+    /// SE rating, if the puzzle could be rated
+    pub rating: Option<f32>,
+    pub hardest: Option<String>,
+}
+
+/// A game in progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u64,
+    pub metadata: Metadata,
+    pub variant: Variant,
+    /// The puzzle as given, as a line of 81 characters
+    pub puzzle: String,
+    pub solution: String,
+    pub board: Sudoku,
+    pub focus: Coord,
+    pub history: UndoTree,
+    /// Time spent playing
+    pub elapsed_ms: u64,
+    pub moves: usize,
+    pub undos: usize,
+    pub hints: usize,
+    pub settings: Settings,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Written by a newer version of the game
+    Version(u64),
+    /// Well-formed, but not a game that can be played
+    Invalid(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Json(e) => write!(f, "{}", e),
+            Self::Version(v) => write!(f, "unsupported version {} (newest is {})", v, VERSION),
+            Self::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl SavedGame {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("saved games are always valid JSON")
+    }
+
+    /// Read a saved game of any version.
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        let value = migrate(serde_json::from_str(json)?)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_json())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// Where games are saved unless the player chooses another file
+pub fn default_path() -> PathBuf {
+    crate::pack::data_dir()
+        .map(|dir| dir.join("save.json"))
+        .unwrap_or_else(|| PathBuf::from("save.json"))
+}
+
+/// Bring a document of any version up to `VERSION`.
+fn migrate(mut value: Value) -> Result<Value, SaveError> {
+    loop {
+        let version = value.get("version").map_or(Some(0), Value::as_u64);
+        value = match version {
+            Some(0) => from_v0(value)?,
+            Some(VERSION) => return Ok(value),
+            Some(v) => return Err(SaveError::Version(v)),
+            None => return Err(SaveError::Invalid("bad version".to_owned())),
+        };
+    }
+}
+
+/// A puzzle from `sudoku generate --json`, which has not been played yet
+fn from_v0(value: Value) -> Result<Value, SaveError> {
+    // a whole batch starts with its first puzzle
+    if let Some(puzzles) = value.get("puzzles") {
+        return match puzzles.get(0) {
+            Some(first) => from_v0(first.clone()),
+            None => Err(SaveError::Invalid("no puzzle".to_owned())),
+        };
+    }
+    let field = |name: &str| value.get(name).and_then(Value::as_str);
+    let puzzle = field("puzzle").ok_or_else(|| SaveError::Invalid("no puzzle".to_owned()))?;
+    let board: Sudoku = puzzle
+        .parse()
+        .map_err(|e| SaveError::Invalid(format!("puzzle: {}", e)))?;
+    let solution = match field("solution") {
+        Some(solution) => solution.to_owned(),
+        None => crate::generator::solve_unique(&board)
+            .map(|ans| Sudoku::from_matrix(ans).to_line())
+            .ok_or_else(|| SaveError::Invalid("no unique solution".to_owned()))?,
+    };
+    let focus = (0..81)
+        .find(|&x| board.available[x / 9][x % 9])
+        .unwrap_or(0);
+    let game = SavedGame {
+        version: 1,
+        metadata: Metadata {
+            name: field("name").map(str::to_owned),
+            symmetry: Symmetry::detect(&board.matrix().concat()),
+            rating: value
+                .get("rating")
+                .and_then(Value::as_f64)
+                .map(|r| r as f32),
+            hardest: field("hardest").map(str::to_owned),
+        },
+        variant: match value.get("variant") {
+            Some(v) => serde_json::from_value(v.clone())?,
+            None => Variant::Classic,
+        },
+        puzzle: board.to_line(),
+        solution,
+        board,
+        focus: [focus / 9, focus % 9],
+        history: UndoTree::new(),
+        elapsed_ms: 0,
+        moves: 0,
+        undos: 0,
+        hints: 0,
+        settings: Settings::default(),
+    };
+    Ok(serde_json::to_value(game)?)
+}
@@ -0,0 +1,515 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Logical solver and difficulty rating.
+//!
+//! The solver only uses techniques a human would, always picking the easiest
+//! one that makes progress, so the hardest technique it needs says how hard a
+//! puzzle is. Techniques are rated on the scale used by Sudoku Explainer (SE).
+
+use crate::sudoku::{Coord, Sudoku};
+use std::fmt;
+
+/// Solving techniques, from the easiest to the hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    HiddenSingleBox,
+    HiddenSingle,
+    NakedSingle,
+    Pointing,
+    Claiming,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XYWing,
+    NakedQuad,
+    Jellyfish,
+    HiddenQuad,
+}
+
+impl Technique {
+    /// The SE rating of the technique
+    pub fn rating(self) -> f32 {
+        use Technique::*;
+        match self {
+            HiddenSingleBox => 1.2,
+            HiddenSingle => 1.5,
+            NakedSingle => 2.3,
+            Pointing => 2.6,
+            Claiming => 2.8,
+            NakedPair => 3.0,
+            XWing => 3.2,
+            HiddenPair => 3.4,
+            NakedTriple => 3.6,
+            Swordfish => 3.8,
+            HiddenTriple => 4.0,
+            XYWing => 4.2,
+            NakedQuad => 5.0,
+            Jellyfish => 5.2,
+            HiddenQuad => 5.4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        use Technique::*;
+        match self {
+            HiddenSingleBox => "Hidden Single (box)",
+            HiddenSingle => "Hidden Single",
+            NakedSingle => "Naked Single",
+            Pointing => "Pointing",
+            Claiming => "Claiming",
+            NakedPair => "Naked Pair",
+            XWing => "X-Wing",
+            HiddenPair => "Hidden Pair",
+            NakedTriple => "Naked Triple",
+            Swordfish => "Swordfish",
+            HiddenTriple => "Hidden Triple",
+            XYWing => "XY-Wing",
+            NakedQuad => "Naked Quad",
+            Jellyfish => "Jellyfish",
+            HiddenQuad => "Hidden Quad",
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// One deduction made by the solver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub technique: Technique,
+    /// The digit placed, for singles
+    pub placement: Option<(Coord, u8)>,
+    /// Number of candidates removed, for all other techniques
+    pub eliminations: usize,
+}
+
+/// How hard a puzzle is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    /// SE rating of the hardest step
+    pub rating: f32,
+    pub hardest: Option<Technique>,
+    /// Number of deductions needed
+    pub steps: usize,
+    /// `false` if the solver got stuck, in which case the puzzle is harder
+    /// than `rating`, or has no unique solution
+    pub solved: bool,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.solved, self.hardest) {
+            (true, Some(t)) => write!(f, "{:.1} ({}, {} steps)", self.rating, t, self.steps),
+            (true, None) => write!(f, "0.0 (already solved)"),
+            (false, _) => write!(f, "> {:.1} (too hard to rate)", self.rating),
+        }
+    }
+}
+
+/// Rate a puzzle by solving it logically.
+pub fn rate(sudoku: &Sudoku) -> Rating {
+    let mut solver = Solver::new(sudoku);
+    let steps = solver.solve();
+    let hardest = steps.iter().map(|s| s.technique).max();
+    Rating {
+        rating: hardest.map_or(0.0, Technique::rating),
+        hardest,
+        steps: steps.len(),
+        solved: solver.is_solved(),
+    }
+}
+
+/// All 27 units: rows, then columns, then boxes.
+fn units() -> [[Coord; 9]; 27] {
+    let mut units = [[[0; 2]; 9]; 27];
+    for x in 0..81 {
+        let (a, b) = (x / 9, x % 9);
+        units[a][b] = [a, b];
+        units[9 + a][b] = [b, a];
+        units[18 + a][b] = [a / 3 * 3 + b / 3, a % 3 * 3 + b % 3];
+    }
+    units
+}
+
+fn is_peer(a: Coord, b: Coord) -> bool {
+    a != b && (a[0] == b[0] || a[1] == b[1] || (a[0] / 3 == b[0] / 3 && a[1] / 3 == b[1] / 3))
+}
+
+/// All ways to choose `n` of `items`.
+fn combinations(items: &[usize], n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut res = Vec::new();
+    for (k, &first) in items.iter().enumerate() {
+        for mut rest in combinations(&items[k + 1..], n - 1) {
+            rest.insert(0, first);
+            res.push(rest);
+        }
+    }
+    res
+}
+
+/// A technique which only removes candidates, returning whether it did.
+type Elimination = fn(&mut Solver) -> bool;
+
+/// Solves a puzzle step by step with human techniques.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    values: [[u8; 9]; 9],
+    /// Bit `v` is set if `v` is still possible in the cell
+    candidates: [[u16; 9]; 9],
+    units: [[Coord; 9]; 27],
+}
+
+impl Solver {
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let mut values = [[0; 9]; 9];
+        let mut candidates = [[0; 9]; 9];
+        for i in 0..9 {
+            for j in 0..9 {
+                values[i][j] = sudoku[[i, j]];
+                candidates[i][j] = sudoku.candidates([i, j]);
+            }
+        }
+        Self {
+            values,
+            candidates,
+            units: units(),
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|row| row.iter().all(|&v| v != 0))
+    }
+
+    /// The digits found so far, `0` for unknown cells.
+    pub fn values(&self) -> [[u8; 9]; 9] {
+        self.values
+    }
+
+    /// Apply steps until the puzzle is solved or no technique applies.
+    pub fn solve(&mut self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while !self.is_solved() {
+            match self.step() {
+                Some(step) => steps.push(step),
+                None => break,
+            }
+        }
+        steps
+    }
+
+    /// Make one deduction with the easiest applicable technique.
+    pub fn step(&mut self) -> Option<Step> {
+        use Technique::*;
+        if let Some(p) = self.hidden_single(true) {
+            return Some(self.placed(HiddenSingleBox, p));
+        }
+        if let Some(p) = self.hidden_single(false) {
+            return Some(self.placed(HiddenSingle, p));
+        }
+        if let Some(p) = self.naked_single() {
+            return Some(self.placed(NakedSingle, p));
+        }
+        let before = self.candidate_count();
+        let progress = |solver: &Self| solver.candidate_count() < before;
+        let techniques: [(Technique, Elimination); 12] = [
+            (Pointing, |s| s.pointing()),
+            (Claiming, |s| s.claiming()),
+            (NakedPair, |s| s.naked_subset(2)),
+            (XWing, |s| s.fish(2)),
+            (HiddenPair, |s| s.hidden_subset(2)),
+            (NakedTriple, |s| s.naked_subset(3)),
+            (Swordfish, |s| s.fish(3)),
+            (HiddenTriple, |s| s.hidden_subset(3)),
+            (XYWing, |s| s.xy_wing()),
+            (NakedQuad, |s| s.naked_subset(4)),
+            (Jellyfish, |s| s.fish(4)),
+            (HiddenQuad, |s| s.hidden_subset(4)),
+        ];
+        for &(technique, apply) in techniques.iter() {
+            if apply(self) && progress(self) {
+                return Some(Step {
+                    technique,
+                    placement: None,
+                    eliminations: before - self.candidate_count(),
+                });
+            }
+        }
+        None
+    }
+
+    fn placed(&mut self, technique: Technique, (coord, v): (Coord, u8)) -> Step {
+        self.place(coord, v);
+        Step {
+            technique,
+            placement: Some((coord, v)),
+            eliminations: 0,
+        }
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidates
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|c| c.count_ones() as usize)
+            .sum()
+    }
+
+    fn cands(&self, [i, j]: Coord) -> u16 {
+        self.candidates[i][j]
+    }
+
+    fn place(&mut self, coord: Coord, v: u8) {
+        let [i, j] = coord;
+        self.values[i][j] = v;
+        self.candidates[i][j] = 0;
+        for [pi, pj] in Sudoku::peers(coord) {
+            self.candidates[pi][pj] &= !(1 << v);
+        }
+    }
+
+    /// Remove `mask` from the candidates of a cell, returning whether any were
+    /// removed.
+    fn eliminate(&mut self, [i, j]: Coord, mask: u16) -> bool {
+        let before = self.candidates[i][j];
+        self.candidates[i][j] &= !mask;
+        before != self.candidates[i][j]
+    }
+
+    /// Bitmask of the positions (0-8) of candidate `v` within a unit.
+    fn positions(&self, unit: &[Coord; 9], v: u8) -> u16 {
+        let mut mask = 0;
+        for (k, &c) in unit.iter().enumerate() {
+            if self.cands(c) & (1 << v) != 0 {
+                mask |= 1 << k;
+            }
+        }
+        mask
+    }
+
+    fn hidden_single(&self, boxes: bool) -> Option<(Coord, u8)> {
+        let range = if boxes { 18..27 } else { 0..18 };
+        for unit in &self.units[range] {
+            for v in 1..=9 {
+                let pos = self.positions(unit, v);
+                if pos.count_ones() == 1 {
+                    return Some((unit[pos.trailing_zeros() as usize], v));
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_single(&self) -> Option<(Coord, u8)> {
+        for i in 0..9 {
+            for j in 0..9 {
+                let c = self.candidates[i][j];
+                if c.count_ones() == 1 {
+                    return Some(([i, j], c.trailing_zeros() as u8));
+                }
+            }
+        }
+        None
+    }
+
+    /// Candidates of a digit in a box confined to one line eliminate the
+    /// digit from the rest of the line.
+    fn pointing(&mut self) -> bool {
+        for b in 18..27 {
+            let unit = self.units[b];
+            for v in 1..=9 {
+                let cells: Vec<Coord> = unit
+                    .iter()
+                    .copied()
+                    .filter(|&c| self.cands(c) & (1 << v) != 0)
+                    .collect();
+                if cells.len() < 2 {
+                    continue;
+                }
+                for axis in 0..2 {
+                    if cells.iter().all(|c| c[axis] == cells[0][axis]) {
+                        let line = self.units[axis * 9 + cells[0][axis]];
+                        let mut changed = false;
+                        for c in line.iter().copied().filter(|c| !unit.contains(c)) {
+                            changed |= self.eliminate(c, 1 << v);
+                        }
+                        if changed {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Candidates of a digit in a line confined to one box eliminate the
+    /// digit from the rest of the box.
+    fn claiming(&mut self) -> bool {
+        for l in 0..18 {
+            let line = self.units[l];
+            for v in 1..=9 {
+                let cells: Vec<Coord> = line
+                    .iter()
+                    .copied()
+                    .filter(|&c| self.cands(c) & (1 << v) != 0)
+                    .collect();
+                if cells.len() < 2 {
+                    continue;
+                }
+                let box_of = |[i, j]: Coord| i / 3 * 3 + j / 3;
+                if cells.iter().all(|&c| box_of(c) == box_of(cells[0])) {
+                    let unit = self.units[18 + box_of(cells[0])];
+                    let mut changed = false;
+                    for c in unit.iter().copied().filter(|c| !line.contains(c)) {
+                        changed |= self.eliminate(c, 1 << v);
+                    }
+                    if changed {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// `n` cells of a unit with only `n` candidates between them.
+    fn naked_subset(&mut self, n: usize) -> bool {
+        for u in 0..27 {
+            let unit = self.units[u];
+            let cells: Vec<usize> = (0..9)
+                .filter(|&k| {
+                    let c = self.cands(unit[k]).count_ones() as usize;
+                    c >= 2 && c <= n
+                })
+                .collect();
+            for combo in combinations(&cells, n) {
+                let union = combo.iter().fold(0, |acc, &k| acc | self.cands(unit[k]));
+                if union.count_ones() as usize != n {
+                    continue;
+                }
+                let mut changed = false;
+                for k in (0..9).filter(|k| !combo.contains(k)) {
+                    changed |= self.eliminate(unit[k], union);
+                }
+                if changed {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// `n` digits of a unit confined to only `n` cells.
+    fn hidden_subset(&mut self, n: usize) -> bool {
+        for u in 0..27 {
+            let unit = self.units[u];
+            let digits: Vec<usize> = (1..=9)
+                .filter(|&v| {
+                    let c = self.positions(&unit, v as u8).count_ones() as usize;
+                    c >= 2 && c <= n
+                })
+                .collect();
+            for combo in combinations(&digits, n) {
+                let union = combo
+                    .iter()
+                    .fold(0, |acc, &v| acc | self.positions(&unit, v as u8));
+                if union.count_ones() as usize != n {
+                    continue;
+                }
+                let keep = combo.iter().fold(0u16, |acc, &v| acc | 1 << v);
+                let mut changed = false;
+                for k in (0..9).filter(|k| union & (1 << k) != 0) {
+                    changed |= self.eliminate(unit[k], !keep);
+                }
+                if changed {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// X-Wing (`n = 2`), Swordfish (`3`) and Jellyfish (`4`).
+    fn fish(&mut self, n: usize) -> bool {
+        for v in 1..=9u8 {
+            // rows as base lines, then columns
+            for base in [0usize, 9].iter().copied() {
+                let cover = 9 - base;
+                let lines: Vec<usize> = (0..9)
+                    .filter(|&l| {
+                        let c = self.positions(&self.units[base + l], v).count_ones() as usize;
+                        c >= 2 && c <= n
+                    })
+                    .collect();
+                for combo in combinations(&lines, n) {
+                    let union = combo
+                        .iter()
+                        .fold(0, |acc, &l| acc | self.positions(&self.units[base + l], v));
+                    if union.count_ones() as usize != n {
+                        continue;
+                    }
+                    let mut changed = false;
+                    for k in (0..9).filter(|k| union & (1 << k) != 0) {
+                        let line = self.units[cover + k];
+                        for (l, &c) in line.iter().enumerate() {
+                            if !combo.contains(&l) {
+                                changed |= self.eliminate(c, 1 << v);
+                            }
+                        }
+                    }
+                    if changed {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// A bivalue pivot `xy` sees two pincers `xz` and `yz`; any cell seeing
+    /// both pincers cannot be `z`.
+    fn xy_wing(&mut self) -> bool {
+        let bivalue: Vec<Coord> = (0..81)
+            .map(|x| [x / 9, x % 9])
+            .filter(|&c| self.cands(c).count_ones() == 2)
+            .collect();
+        for &pivot in &bivalue {
+            let xy = self.cands(pivot);
+            for &a in bivalue.iter().filter(|&&a| is_peer(pivot, a)) {
+                for &b in bivalue.iter().filter(|&&b| is_peer(pivot, b)) {
+                    let (ca, cb) = (self.cands(a), self.cands(b));
+                    if a >= b || ca == cb || ca == xy || cb == xy {
+                        continue;
+                    }
+                    let z = ca & cb;
+                    if z.count_ones() != 1 || (ca | cb) & !z != xy {
+                        continue;
+                    }
+                    let mut changed = false;
+                    for c in (0..81).map(|x| [x / 9, x % 9]) {
+                        if is_peer(a, c) && is_peer(b, c) {
+                            changed |= self.eliminate(c, z);
+                        }
+                    }
+                    if changed {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
@@ -1,20 +1,102 @@
+use serde::{Deserialize, Serialize};
+
 pub type SudokuMatrix = [[u8; 9]; 9];
-type Coord = [usize; 2];
+pub type Coord = [usize; 2];
+
+/// Number of colors cells can be marked with, besides no color
+pub const COLORS: u8 = 6;
+
+/// Everything that is known about a single cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Cell {
+    /// The digit in the cell, or `0` if empty
+    pub value: u8,
+    /// Pencil marks; bit `v` is set if `v` is marked
+    pub notes: u16,
+    /// Automatic candidates removed by the player, in the same format as `notes`
+    pub eliminated: u16,
+    /// `false` for givens (including revealed hints)
+    pub available: bool,
+    /// Color the player marked the cell with, from 1 to [`COLORS`], or `0`
+    #[serde(default)]
+    pub color: u8,
+}
+
+/// A row, column or box, numbered from 0. Boxes are numbered in reading
+/// order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+}
+
+impl Unit {
+    /// The row, column and box of a cell
+    pub fn of([i, j]: Coord) -> [Self; 3] {
+        [Self::Row(i), Self::Column(j), Self::Box(i / 3 * 3 + j / 3)]
+    }
+
+    pub fn cells(self) -> impl Iterator<Item = Coord> {
+        (0..9).map(move |x| match self {
+            Self::Row(i) => [i, x],
+            Self::Column(j) => [x, j],
+            Self::Box(b) => [b / 3 * 3 + x / 3, b % 3 * 3 + x % 3],
+        })
+    }
+
+    pub fn contains(self, coord: Coord) -> bool {
+        Self::of(coord).contains(&self)
+    }
+}
 
-#[derive(Debug)]
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Row(i) => write!(f, "row {}", i + 1),
+            Self::Column(j) => write!(f, "column {}", j + 1),
+            Self::Box(b) => write!(f, "box {}", b + 1),
+        }
+    }
+}
+
+/// Every digit, in the same format as `Cell::notes`
+const ALL: u16 = 0b11_1111_1110;
+
+/// A sudoku board: digits, givens, pencil marks, eliminations and colors.
+///
+/// The digits present in every row, column and box are kept as bitmasks which
+/// are updated as cells change, so that checking for conflicts, computing
+/// candidates and detecting completion take constant time. For this reason
+/// digits are changed with [`set`](Self::set) rather than through indexing.
+///
+/// In JSON, a board is written as two lines of 81 characters in the format of
+/// [`to_line`](Self::to_line), one with the digits the player cannot change
+/// and one with those they entered, and the notes and eliminations of every
+/// cell as bitmasks in reading order, followed by the colors of the cells if
+/// any are colored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "SudokuRepr", try_from = "SudokuRepr")]
 pub struct Sudoku {
     matrix: SudokuMatrix,
     pub available: [[bool; 9]; 9],
+    pub notes: [[u16; 9]; 9],
+    pub eliminated: [[u16; 9]; 9],
+    /// Colors the cells are marked with, as in `Cell::color`
+    pub colors: [[u8; 9]; 9],
+    /// Digits present in each unit: rows, then columns, then boxes
+    masks: [u16; 27],
+    /// How many times each digit appears in each unit, so that a digit stays
+    /// in `masks` while there is another copy of it
+    counts: [[u8; 10]; 27],
+    /// Number of non-empty cells
+    filled: u8,
+    /// Number of digits which appear more than once in a unit, counted once
+    /// per unit
+    duplicates: u8,
 }
 
 impl Sudoku {
-    // fn new(matrix: SudokuMatrix) -> Self {
-    //     Self {
-    //         matrix,
-    //         coords: Self::find_empty_coords(&matrix),
-    //     }
-    // }
-
     fn find_availability(sudoku: &SudokuMatrix) -> [[bool; 9]; 9] {
         let mut available = [[false; 9]; 9];
         for i in 0..9 {
@@ -30,72 +112,283 @@ impl Sudoku {
         matrix.into()
     }
 
-    pub fn finished(&self) -> bool {
+    pub fn matrix(&self) -> SudokuMatrix {
+        self.matrix
+    }
+
+    /// The puzzle as a line of 81 characters, with `.` for empty cells.
+    pub fn to_line(&self) -> String {
+        let mut s = String::with_capacity(81);
         for i in 0..9 {
             for j in 0..9 {
-                if self[[i, j]] == 0 {
-                    return false;
+                match self[[i, j]] {
+                    0 => s.push('.'),
+                    v => s.push((b'0' + v) as char),
                 }
             }
         }
-        true
+        s
     }
 
-    // /// Check whether the sudoku is still valid
-    pub fn conflict(&self, v: u8, coord: Coord) -> Option<[usize; 2]> {
-        if let Some(coord) = self.conflict_row(v, coord) {
-            Some(coord)
-        } else if let Some(coord) = self.conflict_col(v, coord) {
-            Some(coord)
-        } else if let Some(coord) = self.conflict_box(v, coord) {
-            Some(coord)
-        } else {
-            None
-        }
-    }
-
-    fn conflict_row(&self, v: u8, coord: Coord) -> Option<[usize; 2]> {
-        let [i, _] = coord;
-        for j in 0..9 {
-            if self[[i, j]] == v {
-                return Some([i, j]);
+    /// The row, column and box containing a cell, as indices into `masks`
+    fn units(coord: Coord) -> [usize; 3] {
+        let [i, j] = coord;
+        [i, 9 + j, 18 + i / 3 * 3 + j / 3]
+    }
+
+    /// Cells of the `u`th unit, numbered as in `units`
+    fn unit(u: usize) -> impl Iterator<Item = Coord> {
+        let k = u % 9;
+        (0..9).map(move |x| match u / 9 {
+            0 => [k, x],
+            1 => [x, k],
+            _ => [k / 3 * 3 + x / 3, k % 3 * 3 + x % 3],
+        })
+    }
+
+    /// Write a digit (or `0` to empty the cell).
+    pub fn set(&mut self, coord: Coord, v: u8) {
+        let [i, j] = coord;
+        let old = self.matrix[i][j];
+        if old == v {
+            return;
+        }
+        for u in Self::units(coord).iter().copied() {
+            if old != 0 {
+                self.counts[u][old as usize] -= 1;
+                match self.counts[u][old as usize] {
+                    0 => self.masks[u] &= !(1 << old),
+                    1 => self.duplicates -= 1,
+                    _ => {}
+                }
+            }
+            if v != 0 {
+                self.counts[u][v as usize] += 1;
+                self.masks[u] |= 1 << v;
+                if self.counts[u][v as usize] == 2 {
+                    self.duplicates += 1;
+                }
             }
         }
-        None
+        match (old, v) {
+            (0, _) => self.filled += 1,
+            (_, 0) => self.filled -= 1,
+            _ => {}
+        }
+        self.matrix[i][j] = v;
     }
-    fn conflict_col(&self, v: u8, coord: Coord) -> Option<[usize; 2]> {
-        let [_, j] = coord;
-        for i in 0..9 {
-            if self[[i, j]] == v {
-                return Some([i, j]);
-            }
+
+    pub fn cell(&self, coord: Coord) -> Cell {
+        let [i, j] = coord;
+        Cell {
+            value: self[coord],
+            notes: self.notes[i][j],
+            eliminated: self.eliminated[i][j],
+            available: self.available[i][j],
+            color: self.colors[i][j],
         }
-        None
     }
-    fn conflict_box(&self, v: u8, coord: Coord) -> Option<[usize; 2]> {
-        let [i_, j_] = coord;
-        let [i_, j_] = [i_ / 3, j_ / 3];
-        for i in 3 * i_..3 * i_ + 3 {
-            // "inner" i and j; indexes of individual cells
-            for j in 3 * j_..3 * j_ + 3 {
-                if v == self[[i, j]] {
-                    return Some([i, j]);
-                }
-            }
+
+    pub fn set_cell(&mut self, coord: Coord, cell: Cell) {
+        let [i, j] = coord;
+        self.set(coord, cell.value);
+        self.notes[i][j] = cell.notes;
+        self.eliminated[i][j] = cell.eliminated;
+        self.available[i][j] = cell.available;
+        self.colors[i][j] = cell.color;
+    }
+
+    /// The pencil marks of a cell, in ascending order
+    pub fn notes(&self, coord: Coord) -> Vec<u8> {
+        let [i, j] = coord;
+        (1..=9)
+            .filter(|v| self.notes[i][j] & (1 << v) != 0)
+            .collect()
+    }
+
+    /// Digits which can legally be placed in an empty cell, in the same format
+    /// as `notes`. Filled cells have no candidates.
+    pub fn candidates(&self, coord: Coord) -> u16 {
+        if self[coord] != 0 {
+            return 0;
+        }
+        let [r, c, b] = Self::units(coord);
+        ALL & !(self.masks[r] | self.masks[c] | self.masks[b])
+    }
+
+    /// Cells sharing a row, column or box with `coord`, excluding itself.
+    pub fn peers(coord: Coord) -> impl Iterator<Item = Coord> {
+        let [i, j] = coord;
+        let [bi, bj] = [i / 3 * 3, j / 3 * 3];
+        let row = (0..9).map(move |j| [i, j]);
+        let col = (0..9).map(move |i| [i, j]);
+        let box_ = (0..9).map(move |k| [bi + k / 3, bj + k % 3]);
+        row.chain(col)
+            .chain(box_.filter(move |&[i_, j_]| i_ != i && j_ != j))
+            .filter(move |&c| c != coord)
+    }
+
+    /// How many times `v` is on the board
+    ///
+    /// Panics if `v` is greater than 9.
+    pub fn count(&self, v: u8) -> usize {
+        self.counts[..9]
+            .iter()
+            .map(|row| row[v as usize] as usize)
+            .sum()
+    }
+
+    /// Whether every cell is filled
+    pub fn is_full(&self) -> bool {
+        self.filled == 81
+    }
+
+    /// Whether no digit appears twice in a row, column or box
+    pub fn is_valid(&self) -> bool {
+        self.duplicates == 0
+    }
+
+    /// Whether every cell is filled without breaking the rules
+    pub fn finished(&self) -> bool {
+        self.is_full() && self.is_valid()
+    }
+
+    /// Cells whose digit appears more than once in their row, column or box,
+    /// in reading order
+    pub fn errors(&self) -> Vec<Coord> {
+        if self.is_valid() {
+            return Vec::new();
         }
-        None
+        let mut cells: Vec<Coord> = (0..27)
+            .flat_map(|u| Self::unit(u).filter(move |&c| self.counts[u][self[c] as usize] > 1))
+            .filter(|&c| self[c] != 0)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    /// A cell in the same row, column or box as `coord` (in that order of
+    /// preference) which already holds `v`.
+    pub fn conflict(&self, v: u8, coord: Coord) -> Option<[usize; 2]> {
+        Self::units(coord)
+            .iter()
+            .copied()
+            .find(|&u| self.masks[u] & (1 << v) != 0)
+            .and_then(|u| Self::unit(u).find(|&c| self[c] == v))
+    }
+
+    /// Every other cell in the row, column or box of `coord` which holds `v`,
+    /// in reading order.
+    pub fn conflicts(&self, v: u8, coord: Coord) -> Vec<Coord> {
+        let mut cells: Vec<Coord> = Self::units(coord)
+            .iter()
+            .filter(|&&u| self.masks[u] & (1 << v) != 0)
+            .flat_map(|&u| Self::unit(u))
+            .filter(|&c| c != coord && self[c] == v)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
     }
 }
 
 impl std::convert::From<SudokuMatrix> for Sudoku {
     fn from(matrix: SudokuMatrix) -> Self {
-        Self {
-            matrix,
+        let mut sudoku = Self {
+            matrix: [[0; 9]; 9],
             available: Self::find_availability(&matrix),
+            notes: [[0; 9]; 9],
+            eliminated: [[0; 9]; 9],
+            colors: [[0; 9]; 9],
+            masks: [0; 27],
+            counts: [[0; 10]; 27],
+            filled: 0,
+            duplicates: 0,
+        };
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, &v) in row.iter().enumerate() {
+                sudoku.set([i, j], v);
+            }
+        }
+        sudoku
+    }
+}
+
+/// How a `Sudoku` is serialized
+#[derive(Serialize, Deserialize)]
+struct SudokuRepr {
+    givens: String,
+    entries: String,
+    notes: Vec<u16>,
+    eliminated: Vec<u16>,
+    /// Left out while no cell is colored
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    colors: Vec<u8>,
+}
+
+impl From<Sudoku> for SudokuRepr {
+    fn from(sudoku: Sudoku) -> Self {
+        let line = |given: bool| {
+            let mut s = String::with_capacity(81);
+            for i in 0..9 {
+                for j in 0..9 {
+                    match sudoku[[i, j]] {
+                        v if v > 0 && sudoku.available[i][j] != given => s.push((b'0' + v) as char),
+                        _ => s.push('.'),
+                    }
+                }
+            }
+            s
+        };
+        Self {
+            givens: line(true),
+            entries: line(false),
+            notes: sudoku.notes.iter().flatten().copied().collect(),
+            eliminated: sudoku.eliminated.iter().flatten().copied().collect(),
+            colors: if sudoku.colors.iter().flatten().any(|&c| c != 0) {
+                sudoku.colors.iter().flatten().copied().collect()
+            } else {
+                Vec::new()
+            },
         }
     }
 }
 
+impl std::convert::TryFrom<SudokuRepr> for Sudoku {
+    type Error = ParseSudokuError;
+
+    fn try_from(repr: SudokuRepr) -> Result<Self, Self::Error> {
+        let mut sudoku: Sudoku = repr.givens.parse()?;
+        let entries: Sudoku = repr.entries.parse()?;
+        for masks in [&repr.notes, &repr.eliminated].iter() {
+            if masks.len() != 81 {
+                return Err(ParseSudokuError::Length(masks.len()));
+            }
+        }
+        if !repr.colors.is_empty() && repr.colors.len() != 81 {
+            return Err(ParseSudokuError::Length(repr.colors.len()));
+        }
+        if let Some(&c) = repr.colors.iter().find(|&&c| c > COLORS) {
+            return Err(ParseSudokuError::Color(c));
+        }
+        for x in 0..81 {
+            let coord = [x / 9, x % 9];
+            let [i, j] = coord;
+            if entries[coord] != 0 {
+                if sudoku[coord] != 0 {
+                    return Err(ParseSudokuError::Overlap(coord));
+                }
+                sudoku.set(coord, entries[coord]);
+            }
+            sudoku.notes[i][j] = repr.notes[x];
+            sudoku.eliminated[i][j] = repr.eliminated[x];
+            sudoku.colors[i][j] = repr.colors.get(x).copied().unwrap_or(0);
+        }
+        Ok(sudoku)
+    }
+}
+
 impl std::ops::Index<Coord> for Sudoku {
     type Output = u8;
 
@@ -104,13 +397,58 @@ impl std::ops::Index<Coord> for Sudoku {
     }
 }
 
-impl std::ops::IndexMut<Coord> for Sudoku {
-    fn index_mut(&mut self, coords: Coord) -> &mut u8 {
-        &mut self.matrix[coords[0]][coords[1]]
+use std::fmt;
+
+/// Why a puzzle could not be read from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSudokuError {
+    /// The puzzle did not have 81 cells
+    Length(usize),
+    InvalidChar(char),
+    /// A cell was both given and entered by the player
+    Overlap(Coord),
+    /// A cell was marked with a color that does not exist
+    Color(u8),
+}
+
+impl fmt::Display for ParseSudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length(n) => write!(f, "expected 81 cells, found {}", n),
+            Self::InvalidChar(c) => write!(f, "invalid character '{}'", c),
+            Self::Overlap([i, j]) => write!(f, "r{}c{} is both given and entered", i + 1, j + 1),
+            Self::Color(c) => write!(f, "there is no color {}", c),
+        }
     }
 }
 
-use std::fmt;
+impl std::error::Error for ParseSudokuError {}
+
+impl std::str::FromStr for Sudoku {
+    type Err = ParseSudokuError;
+
+    /// Read a puzzle written as 81 characters, row by row, using `0` or `.`
+    /// for empty cells. Whitespace is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut matrix = [[0; 9]; 9];
+        let mut n = 0;
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            let v = match c {
+                '.' => 0,
+                '0'..='9' => c as u8 - b'0',
+                _ => return Err(ParseSudokuError::InvalidChar(c)),
+            };
+            if n < 81 {
+                matrix[n / 9][n % 9] = v;
+            }
+            n += 1;
+        }
+        if n != 81 {
+            return Err(ParseSudokuError::Length(n));
+        }
+        Ok(matrix.into())
+    }
+}
 
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
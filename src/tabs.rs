@@ -0,0 +1,177 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Several boards open at once, one per tab.
+//!
+//! Only the board of the selected tab is shown and receives events; the
+//! timers of the others are paused until they are selected again. Each board
+//! keeps its own game, history and settings.
+
+use crate::board::SudokuBoard;
+use cursive::{
+    event::{Event, EventResult, MouseEvent},
+    theme::ColorStyle,
+    view::View,
+    Printer, Vec2,
+};
+
+/// Longest title shown on a tab
+const TITLE_WIDTH: usize = 14;
+
+#[derive(Debug)]
+pub struct Tabs {
+    boards: Vec<SudokuBoard>,
+    active: usize,
+}
+
+impl Tabs {
+    pub fn new(board: SudokuBoard) -> Self {
+        Self {
+            boards: vec![board],
+            active: 0,
+        }
+    }
+
+    /// The board of the selected tab
+    pub fn board(&self) -> &SudokuBoard {
+        &self.boards[self.active]
+    }
+
+    pub fn board_mut(&mut self) -> &mut SudokuBoard {
+        &mut self.boards[self.active]
+    }
+
+    pub fn boards(&self) -> &[SudokuBoard] {
+        &self.boards
+    }
+
+    /// Index of the selected tab
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+
+    /// Add a tab after the last one and select it.
+    pub fn open(&mut self, board: SudokuBoard) {
+        self.boards.push(board);
+        self.select(self.boards.len() - 1);
+    }
+
+    /// Close the selected tab and select its neighbour. The last tab cannot
+    /// be closed.
+    pub fn close(&mut self) {
+        if self.boards.len() > 1 {
+            self.boards.remove(self.active);
+            self.active = self.active.min(self.boards.len() - 1);
+            self.board_mut().resume();
+        }
+    }
+
+    /// Show the `k`th tab, pausing the one shown before.
+    pub fn select(&mut self, k: usize) {
+        if k < self.boards.len() && k != self.active {
+            self.board_mut().pause();
+            self.active = k;
+            self.board_mut().resume();
+        }
+    }
+
+    /// Select the next or previous tab, wrapping around.
+    pub fn cycle(&mut self, forward: bool) {
+        let n = self.boards.len();
+        let k = if forward {
+            (self.active + 1) % n
+        } else {
+            (self.active + n - 1) % n
+        };
+        self.select(k);
+    }
+
+    /// The text of each tab, e.g. ` 2 Sudoku 3.4 `
+    fn labels(&self) -> Vec<String> {
+        self.boards
+            .iter()
+            .enumerate()
+            .map(|(k, board)| {
+                let title: String = board.title().chars().take(TITLE_WIDTH).collect();
+                format!(" {} {} ", k + 1, title)
+            })
+            .collect()
+    }
+
+    /// The tab drawn at column `x` of the first line
+    fn tab_at(&self, x: usize) -> Option<usize> {
+        let mut end = 0;
+        self.labels().iter().position(|label| {
+            end += label.chars().count();
+            x < end
+        })
+    }
+}
+
+impl View for Tabs {
+    fn draw(&self, printer: &Printer) {
+        let mut x = 0;
+        for (k, label) in self.labels().iter().enumerate() {
+            let style = if k == self.active {
+                ColorStyle::highlight()
+            } else {
+                ColorStyle::secondary()
+            };
+            printer.with_color(style, |p| p.print((x, 0), label));
+            x += label.chars().count();
+        }
+        self.board().draw(&printer.offset((0, 1)));
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        self.board_mut()
+            .required_size(constraint.saturating_sub((0, 1)))
+            + (0, 1)
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        self.board_mut().layout(size.saturating_sub((0, 1)));
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Mouse {
+                offset,
+                position,
+                event,
+            } if position.y == offset.y && position.x >= offset.x => {
+                match (event, self.tab_at(position.x - offset.x)) {
+                    (MouseEvent::Press(_), Some(k)) => {
+                        self.select(k);
+                        EventResult::Consumed(None)
+                    }
+                    _ => EventResult::Ignored,
+                }
+            }
+            Event::Mouse {
+                offset,
+                position,
+                event,
+            } => self.board_mut().on_event(Event::Mouse {
+                offset: offset + (0, 1),
+                position,
+                event,
+            }),
+            event => self.board_mut().on_event(event),
+        }
+    }
+
+    fn take_focus(&mut self, direction: cursive::direction::Direction) -> bool {
+        self.board_mut().take_focus(direction)
+    }
+}
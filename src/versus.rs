@@ -0,0 +1,156 @@
+// Copyright (c) 2020 Tianyi Shi
+//
+// This software is released under the MIT License.
+// https://opensource.org/licenses/MIT
+
+//! Two players taking turns on the same board.
+//!
+//! Each turn, a player enters one digit. A digit agreeing with the solution
+//! is placed and scores [`CORRECT_POINTS`]; any other digit is not placed and
+//! costs [`MISTAKE_POINTS`]. Either way the turn passes to the other player.
+//! Whoever has more points once the board is full wins.
+//!
+//! ```
+//! use sudoku_tui::engine::Game;
+//! use sudoku_tui::versus::{Placement, Versus};
+//!
+//! let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+//! let mut game = Game::from_puzzle(&puzzle.parse().unwrap()).unwrap();
+//! let mut versus = Versus::new();
+//! assert_eq!(versus.place(&mut game, [0, 2], 4), Placement::Correct);
+//! assert_eq!(versus.place(&mut game, [0, 3], 1), Placement::Mistake);
+//! assert_eq!(versus.players()[0].score, 10);
+//! assert_eq!(versus.players()[1].score, -5);
+//! ```
+
+use crate::engine::{Action, Event, Game};
+use crate::sudoku::Coord;
+
+/// Points for a digit agreeing with the solution
+pub const CORRECT_POINTS: i32 = 10;
+/// Points lost for any other digit
+pub const MISTAKE_POINTS: i32 = 5;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Player {
+    pub name: String,
+    pub score: i32,
+    /// Digits placed
+    pub correct: usize,
+    pub mistakes: usize,
+}
+
+/// What became of a digit entered on a player's turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Nothing happened, e.g. the cell is not empty; the turn does not pass
+    Ignored,
+    /// The digit was placed
+    Correct,
+    /// The digit does not agree with the solution and was not placed
+    Mistake,
+    /// The digit was placed and the board is full
+    Completed,
+}
+
+/// A wrong digit, to tell the players about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mistake {
+    pub player: usize,
+    pub coord: Coord,
+    pub value: u8,
+}
+
+/// The scores and turns of a two-player game. The board itself is a [`Game`]
+/// passed to [`Versus::place`].
+#[derive(Debug, Clone)]
+pub struct Versus {
+    players: [Player; 2],
+    turn: usize,
+    /// Who placed the digit of each cell
+    owners: [[Option<usize>; 9]; 9],
+    last_mistake: Option<Mistake>,
+}
+
+impl Default for Versus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Versus {
+    /// A game between "Player 1", who starts, and "Player 2".
+    pub fn new() -> Self {
+        let player = |k: usize| Player {
+            name: format!("Player {}", k),
+            ..Player::default()
+        };
+        Self {
+            players: [player(1), player(2)],
+            turn: 0,
+            owners: [[None; 9]; 9],
+            last_mistake: None,
+        }
+    }
+
+    /// Enter `v` at `coord` for the player whose turn it is.
+    pub fn place(&mut self, game: &mut Game, coord: Coord, v: u8) -> Placement {
+        if !game.is_available(coord) || game.sudoku()[coord] != 0 || !(1..=9).contains(&v) {
+            return Placement::Ignored;
+        }
+        let [i, j] = coord;
+        let player = &mut self.players[self.turn];
+        let placement = if game.solution()[i][j] == v {
+            let placement = match game.apply(Action::Fill(coord, v)) {
+                Event::Completed => Placement::Completed,
+                Event::Changed => Placement::Correct,
+                _ => return Placement::Ignored,
+            };
+            player.score += CORRECT_POINTS;
+            player.correct += 1;
+            self.owners[i][j] = Some(self.turn);
+            self.last_mistake = None;
+            placement
+        } else {
+            player.score -= MISTAKE_POINTS;
+            player.mistakes += 1;
+            self.last_mistake = Some(Mistake {
+                player: self.turn,
+                coord,
+                value: v,
+            });
+            Placement::Mistake
+        };
+        self.turn = 1 - self.turn;
+        placement
+    }
+
+    pub fn players(&self) -> &[Player; 2] {
+        &self.players
+    }
+
+    /// The player to enter the next digit
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// The player who placed the digit at `coord`, if any
+    pub fn owner(&self, coord: Coord) -> Option<usize> {
+        self.owners[coord[0]][coord[1]]
+    }
+
+    /// The latest wrong digit, until a correct one is placed
+    pub fn last_mistake(&self) -> Option<Mistake> {
+        self.last_mistake
+    }
+
+    /// The player with more points, or `None` on a draw
+    pub fn winner(&self) -> Option<usize> {
+        let [a, b] = [self.players[0].score, self.players[1].score];
+        match a.cmp(&b) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use cursive::event::{Event, Key, MouseButton, MouseEvent};
+use cursive::view::View;
+use cursive::Vec2;
+use sudoku_tui::board::SudokuBoard;
+use sudoku_tui::engine::{Game, Settings};
+use sudoku_tui::pack::Pack;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// Row of the digit palette below the board
+const PALETTE: usize = 16;
+
+/// Row of the digit tracker below the palette
+const TRACKER: usize = 17;
+
+fn playing() -> SudokuBoard {
+    let mut board = SudokuBoard::new();
+    board.on_event(Event::Key(Key::Enter));
+    board
+}
+
+fn board() -> SudokuBoard {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    let mut board = SudokuBoard::new();
+    board.resume_game(game);
+    board
+}
+
+fn mouse(board: &mut SudokuBoard, x: usize, y: usize, event: MouseEvent) {
+    board.on_event(Event::Mouse {
+        offset: Vec2::zero(),
+        position: Vec2::new(x, y),
+        event,
+    });
+}
+
+fn click(board: &mut SudokuBoard, x: usize, y: usize) {
+    mouse(board, x, y, MouseEvent::Press(MouseButton::Left));
+}
+
+#[test]
+fn leaving_the_editor_returns_to_the_game() {
+    let mut board = playing();
+    let sudoku = board.game().sudoku().clone();
+    board.edit();
+    assert!(board.is_editing());
+    board.on_event(Event::Char('5'));
+    board.hint();
+    board.bookmark();
+    assert_eq!(board.game().hints(), 0);
+    assert!(board.bookmarks().is_empty());
+
+    board.on_event(Event::Key(Key::Esc));
+    assert!(!board.is_editing());
+    assert_eq!(board.game().sudoku(), &sudoku);
+    // the game goes on where it was left
+    board.hint();
+    assert_eq!(board.game().hints(), 1);
+}
+
+#[test]
+fn clicks_pick_cells_and_palette_buttons() {
+    let mut board = board();
+    // r1c3 is drawn at column 3, row 1; r1c4 after the box border
+    click(&mut board, 3, 1);
+    assert_eq!(board.game().focus(), [0, 2]);
+    click(&mut board, 5, 1);
+    assert_eq!(board.game().focus(), [0, 3]);
+    click(&mut board, 4, 1);
+    assert_eq!(board.game().focus(), [0, 3]);
+
+    // `1 2 3 4 5 6 7 8 9 Del Note`
+    click(&mut board, 10, PALETTE);
+    assert_eq!(board.game().sudoku()[[0, 3]], 6);
+    click(&mut board, 11, PALETTE);
+    assert_eq!(board.game().sudoku()[[0, 3]], 6);
+    click(&mut board, 19, PALETTE);
+    assert_eq!(board.game().sudoku()[[0, 3]], 0);
+    click(&mut board, 24, PALETTE);
+    click(&mut board, 4, PALETTE);
+    assert_eq!(board.game().cell([0, 3]).notes, 1 << 3);
+}
+
+#[test]
+fn the_palette_fits_in_the_view() {
+    let mut board = board();
+    // `Color` ends at column 31
+    assert!(board.required_size(Vec2::zero()).x > 31);
+    // the tracker is the last row
+    assert!(board.required_size(Vec2::zero()).y > TRACKER);
+}
+
+#[test]
+fn right_clicks_cycle_the_pencil_mark() {
+    let mut board = board();
+    let right = MouseEvent::Press(MouseButton::Right);
+    for v in 1..=9 {
+        mouse(&mut board, 3, 1, right);
+        assert_eq!(board.game().cell([0, 2]).notes, 1 << v);
+    }
+    mouse(&mut board, 3, 1, right);
+    assert_eq!(board.game().cell([0, 2]).notes, 0);
+}
+
+#[test]
+fn drags_mark_every_cell_but_digits_only_go_into_the_focused_one() {
+    let mut board = board();
+    click(&mut board, 3, 1);
+    mouse(&mut board, 5, 1, MouseEvent::Hold(MouseButton::Left));
+    assert_eq!(board.game().focus(), [0, 3]);
+    board.on_event(Event::Char('n'));
+    board.on_event(Event::Char('1'));
+    assert_eq!(board.game().cell([0, 2]).notes, 1 << 1);
+    assert_eq!(board.game().cell([0, 3]).notes, 1 << 1);
+
+    board.on_event(Event::Char('n'));
+    board.on_event(Event::Char('6'));
+    assert_eq!(board.game().sudoku()[[0, 3]], 6);
+    assert_eq!(board.game().sudoku()[[0, 2]], 0);
+}
+
+#[test]
+fn a_selection_is_one_turn_in_a_two_player_game() {
+    let mut board = SudokuBoard::new();
+    board.load_pack(Pack::parse("Test", PUZZLE).unwrap());
+    board.start_versus();
+    click(&mut board, 3, 1);
+    mouse(&mut board, 5, 1, MouseEvent::Hold(MouseButton::Left));
+    board.on_event(Event::Char('6'));
+    assert_eq!(board.versus().unwrap().turn(), 1);
+    assert_eq!(board.game().sudoku()[[0, 3]], 6);
+    assert_eq!(board.game().sudoku()[[0, 2]], 0);
+}
+
+#[test]
+fn color_mode_colors_without_alt() {
+    let mut board = board();
+    click(&mut board, 3, 1);
+    board.on_event(Event::Char('k'));
+    board.on_event(Event::Char('3'));
+    assert_eq!(board.game().cell([0, 2]).color, 3);
+    assert_eq!(board.game().sudoku()[[0, 2]], 0);
+    board.on_event(Event::Char('0'));
+    assert_eq!(board.game().cell([0, 2]).color, 0);
+
+    // `Color` after `Note` in the palette; note mode turns it off
+    click(&mut board, 22, PALETTE);
+    click(&mut board, 4, PALETTE);
+    assert_eq!(board.game().cell([0, 2]).notes, 1 << 3);
+    click(&mut board, 27, PALETTE);
+    click(&mut board, 4, PALETTE);
+    assert_eq!(board.game().cell([0, 2]).color, 3);
+    assert_eq!(board.game().cell([0, 2]).notes, 1 << 3);
+}
+
+#[test]
+fn conflicting_digits_are_still_missing() {
+    let mut board = board();
+    board.set_settings(Settings {
+        allow_conflicts: true,
+        ..board.settings()
+    });
+    let before = board.missing();
+    // r1 already has a 5
+    click(&mut board, 3, 1);
+    board.on_event(Event::Char('5'));
+    assert_eq!(board.game().sudoku()[[0, 2]], 5);
+    assert_eq!(board.missing(), before);
+    board.on_event(Event::Char('4'));
+    assert_eq!(board.missing()[3], before[3] - 1);
+}
+
+#[test]
+fn new_games_stop_highlighting() {
+    let mut board = board();
+    click(&mut board, 8, TRACKER);
+    assert_eq!(board.highlighted(), Some(5));
+    board.restart();
+    assert_eq!(board.highlighted(), None);
+
+    click(&mut board, 8, TRACKER);
+    board.resume_game(Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap());
+    assert_eq!(board.highlighted(), None);
+}
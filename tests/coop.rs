@@ -0,0 +1,197 @@
+use cursive::event::Event;
+use cursive::view::View;
+use std::io::Write;
+use std::net::TcpStream;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use sudoku_tui::board::SudokuBoard;
+use sudoku_tui::coop::{Edit, Entry, Host, Partner, State};
+use sudoku_tui::engine::{Game, Settings};
+use sudoku_tui::sudoku::Coord;
+
+/// Wait for the state seen by `partner` to satisfy `done`.
+fn wait_for(partner: &Partner, done: impl Fn(&State) -> bool) -> State {
+    let start = Instant::now();
+    loop {
+        if let Some(state) = partner.state() {
+            if done(&state) {
+                return state;
+            }
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "state: {:?}",
+            partner.state()
+        );
+        sleep(Duration::from_millis(10));
+    }
+}
+
+/// An empty cell of the puzzle and the digit that belongs there
+fn empty_cell(partner: &Partner) -> (Coord, u8) {
+    let game = Game::from_puzzle(partner.puzzle()).unwrap();
+    let ans = game.solution();
+    let [i, j] = (0..81)
+        .map(|x| [x / 9, x % 9])
+        .find(|&c| game.is_available(c))
+        .unwrap();
+    ([i, j], ans[i][j])
+}
+
+#[test]
+fn edits_reach_every_partner() {
+    let host = Host::bind("127.0.0.1:0", 42).unwrap();
+    let mut alice = Partner::join(host.local_addr(), "Alice").unwrap();
+    let bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    assert_eq!((alice.id(), bob.id()), (0, 1));
+    assert_eq!(alice.puzzle(), bob.puzzle());
+    wait_for(&alice, |s| s.members.len() == 2);
+
+    let (coord, value) = empty_cell(&alice);
+    alice.edit(Edit::Fill { coord, value }, 0).unwrap();
+    let state = wait_for(&bob, |s| s.revision == 1);
+    assert_eq!(state.sudoku[coord], value);
+    assert_eq!(state.history.len(), 1);
+    assert_eq!(state.history[0].player, 0);
+
+    alice.move_cursor([4, 5]).unwrap();
+    let state = wait_for(&bob, |s| s.members[0].cursor == [4, 5]);
+    assert_eq!(state.members[0].name, "Alice");
+}
+
+#[test]
+fn the_first_edit_of_a_cell_wins() {
+    let host = Host::bind("127.0.0.1:0", 7).unwrap();
+    let mut alice = Partner::join(host.local_addr(), "Alice").unwrap();
+    let mut bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    wait_for(&bob, |s| s.members.len() == 2);
+
+    let (coord, value) = empty_cell(&alice);
+    alice.edit(Edit::Fill { coord, value }, 0).unwrap();
+    wait_for(&alice, |s| s.revision == 1);
+    // Bob has not seen Alice's digit yet
+    bob.edit(Edit::Clear { coord }, 0).unwrap();
+    let start = Instant::now();
+    while bob.rejection().is_none() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        sleep(Duration::from_millis(10));
+    }
+    assert!(bob.rejection().unwrap().starts_with("Alice changed row"));
+    assert_eq!(wait_for(&bob, |s| s.revision == 1).sudoku[coord], value);
+}
+
+#[test]
+fn undo_is_shared_and_attributed() {
+    let host = Host::bind("127.0.0.1:0", 3).unwrap();
+    let mut alice = Partner::join(host.local_addr(), "Alice").unwrap();
+    let mut bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    wait_for(&alice, |s| s.members.len() == 2);
+
+    let ([i, j], value) = empty_cell(&alice);
+    alice
+        .edit(
+            Edit::Fill {
+                coord: [i, j],
+                value,
+            },
+            0,
+        )
+        .unwrap();
+    wait_for(&bob, |s| s.revision == 1);
+    bob.edit(Edit::Undo, 1).unwrap();
+    let state = wait_for(&alice, |s| s.revision == 2);
+    assert_eq!(state.sudoku[[i, j]], 0);
+    let text = format!("r{}c{}: {}", i + 1, j + 1, value);
+    assert_eq!(
+        state.history,
+        vec![
+            Entry {
+                player: 0,
+                text: text.clone()
+            },
+            Entry {
+                player: 1,
+                text: format!("undid {} (Alice)", text)
+            },
+        ]
+    );
+}
+
+#[test]
+fn leaving_is_reported() {
+    let host = Host::bind("127.0.0.1:0", 5).unwrap();
+    let alice = Partner::join(host.local_addr(), "Alice").unwrap();
+    let bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    wait_for(&alice, |s| s.members.len() == 2);
+    drop(bob);
+    let state = wait_for(&alice, |s| s.members[1].left);
+    assert!(!state.members[0].left);
+    assert!(alice.is_connected());
+}
+
+#[test]
+fn cells_off_the_board_are_ignored() {
+    let host = Host::bind("127.0.0.1:0", 11).unwrap();
+    let mut alice = Partner::join(host.local_addr(), "Alice").unwrap();
+    let mut mallory = TcpStream::connect(host.local_addr()).unwrap();
+    for line in [
+        r#"{"type":"join","name":"Mallory"}"#,
+        r#"{"type":"edit","edit":{"edit":"fill","coord":[9,9],"value":1},"revision":0}"#,
+        r#"{"type":"edit","edit":{"edit":"note","coord":[0,0],"value":12},"revision":0}"#,
+        r#"{"type":"cursor","coord":[4,12]}"#,
+    ] {
+        writeln!(mallory, "{}", line).unwrap();
+    }
+    wait_for(&alice, |s| s.members.len() == 2);
+
+    let (coord, value) = empty_cell(&alice);
+    alice.edit(Edit::Fill { coord, value }, 0).unwrap();
+    let state = wait_for(&alice, |s| s.revision == 1);
+    assert!(state.members.iter().all(|m| m.cursor[1] < 9));
+    let bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    assert_eq!(bob.id(), 2);
+}
+
+#[test]
+fn shared_solves_are_not_personal_bests() {
+    let host = Host::bind("127.0.0.1:0", 13).unwrap();
+    let mut board = SudokuBoard::new();
+    board.start_coop(Partner::join(host.local_addr(), "Alice").unwrap());
+    let mut bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    let game = Game::from_puzzle(bob.puzzle()).unwrap();
+    let ans = game.solution();
+    for coord in (0..81).map(|x| [x / 9, x % 9]) {
+        if game.is_available(coord) {
+            let value = ans[coord[0]][coord[1]];
+            bob.edit(Edit::Fill { coord, value }, 0).unwrap();
+        }
+    }
+
+    let start = Instant::now();
+    while !board.game().is_finished() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        sleep(Duration::from_millis(10));
+        board.sync();
+    }
+    assert!(!board.progress().completed(game.puzzle()));
+}
+
+#[test]
+fn eliminations_of_a_selection_are_shared() {
+    let host = Host::bind("127.0.0.1:0", 17).unwrap();
+    let mut board = SudokuBoard::new();
+    board.set_settings(Settings {
+        auto_candidates: true,
+        ..Settings::default()
+    });
+    board.start_coop(Partner::join(host.local_addr(), "Alice").unwrap());
+    let bob = Partner::join(host.local_addr(), "Bob").unwrap();
+    let [i, j] = board.game().focus();
+    let candidates = board.game().candidates([i, j]);
+    let v = (1..=9).find(|v| candidates & 1 << v != 0).unwrap();
+
+    for c in &['R', 'n', (b'0' + v) as char] {
+        board.on_event(Event::Char(*c));
+    }
+    wait_for(&bob, |s| s.sudoku.notes[i][j] & 1 << v != 0);
+}
@@ -0,0 +1,266 @@
+use sudoku_tui::engine::{Action, Direction, Editor, Event, Game, Settings};
+use sudoku_tui::generator::Solutions;
+use sudoku_tui::sudoku::Unit;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+fn game() -> Game {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    game
+}
+
+#[test]
+fn rejects_puzzles_without_unique_solution() {
+    let empty = ".".repeat(81);
+    assert!(Game::from_puzzle(&empty.parse().unwrap()).is_none());
+}
+
+#[test]
+fn givens_cannot_be_changed() {
+    let mut game = game();
+    assert_eq!(game.apply(Action::Fill([0, 0], 1)), Event::Ignored);
+    assert_eq!(game.apply(Action::Clear([0, 0])), Event::Ignored);
+    assert_eq!(game.cell([0, 0]).value, 5);
+    assert_eq!(game.moves(), 0);
+}
+
+#[test]
+fn cells_off_the_board_are_ignored() {
+    let mut game = game();
+    for coord in [[9, 0], [0, 9], [usize::MAX, 3]] {
+        assert_eq!(game.apply(Action::Fill(coord, 1)), Event::Ignored);
+        assert_eq!(game.apply(Action::Clear(coord)), Event::Ignored);
+        assert_eq!(game.apply(Action::ToggleNote(coord, 1)), Event::Ignored);
+        assert_eq!(game.apply(Action::SetFocus(coord)), Event::Ignored);
+        assert_eq!(game.apply(Action::Select(coord)), Event::Ignored);
+        assert!(!game.is_available(coord));
+        assert_eq!(game.cell(coord).value, 0);
+        assert_eq!(game.candidates(coord), 0);
+    }
+    assert_eq!(game.apply(Action::SelectUnit(Unit::Row(9))), Event::Ignored);
+    assert_eq!(game.selection(), &[game.focus()]);
+    assert_eq!(game.moves(), 0);
+}
+
+#[test]
+fn conflicting_digits_are_rejected() {
+    let mut game = game();
+    assert_eq!(game.apply(Action::Fill([0, 2], 9)), Event::Conflict([2, 1]));
+    let conflict = game.conflict().unwrap();
+    assert_eq!(conflict.with, [[2, 1]]);
+    assert_eq!(conflict.units(), [Unit::Box(0)]);
+    assert_eq!(conflict.to_string(), "9 conflicts with 9 in box 1");
+    game.apply(Action::Fill([0, 2], 5));
+    assert_eq!(
+        game.conflict().unwrap().to_string(),
+        "5 conflicts with 5 in row 1, box 1"
+    );
+    assert_eq!(game.cell([0, 2]).value, 0);
+    assert_eq!(game.apply(Action::Fill([0, 2], 4)), Event::Changed);
+    assert_eq!(game.conflict(), None);
+
+    // every clash is reported, not just the first
+    assert_eq!(game.apply(Action::Fill([0, 3], 5)), Event::Conflict([0, 0]));
+    let conflict = game.conflict().unwrap();
+    assert_eq!(conflict.with, [[0, 0], [1, 5]]);
+    assert_eq!(conflict.units(), [Unit::Row(0), Unit::Box(1)]);
+    assert_eq!(conflict.to_string(), "5 conflicts with 5 in row 1, box 2");
+}
+
+#[test]
+fn conflicting_digits_can_be_allowed() {
+    let mut game = game();
+    game.set_settings(Settings {
+        allow_conflicts: true,
+        ..Settings::default()
+    });
+    assert_eq!(game.apply(Action::Fill([0, 2], 5)), Event::Changed);
+    assert_eq!(game.cell([0, 2]).value, 5);
+    assert_eq!(game.conflict(), None);
+    assert_eq!(game.errors(), [[0, 0], [0, 2]]);
+
+    // a full board with errors is not solved
+    let ans = game.solution();
+    let mut last = Event::Ignored;
+    for x in 0..81 {
+        let [i, j] = [x / 9, x % 9];
+        if game.is_available([i, j]) && [i, j] != [0, 2] {
+            last = game.apply(Action::Fill([i, j], ans[i][j]));
+        }
+    }
+    assert_eq!(last, Event::Changed);
+    assert!(game.sudoku().is_full());
+    assert!(!game.is_finished());
+    assert_eq!(game.apply(Action::Fill([0, 2], 4)), Event::Completed);
+    assert!(game.errors().is_empty());
+}
+
+#[test]
+fn filling_the_solution_completes_the_game() {
+    let mut game = game();
+    let ans = game.solution();
+    let empty: Vec<[usize; 2]> = (0..81)
+        .map(|x| [x / 9, x % 9])
+        .filter(|&c| game.is_available(c))
+        .collect();
+    let (last, rest) = empty.split_last().unwrap();
+    for &[i, j] in rest {
+        assert_eq!(game.apply(Action::Fill([i, j], ans[i][j])), Event::Changed);
+    }
+    assert!(!game.is_finished());
+    let [i, j] = *last;
+    assert_eq!(
+        game.apply(Action::Fill([i, j], ans[i][j])),
+        Event::Completed
+    );
+    assert!(game.is_finished());
+}
+
+#[test]
+fn hints_and_undo_are_counted() {
+    let mut game = game();
+    game.apply(Action::Hint);
+    assert_eq!(game.hints(), 1);
+    assert_eq!(game.apply(Action::Undo), Event::Changed);
+    assert_eq!(game.undos(), 1);
+    assert_eq!(game.sudoku(), game.puzzle());
+    assert_eq!(game.apply(Action::Undo), Event::Ignored);
+}
+
+#[test]
+fn notes_become_eliminations_with_automatic_candidates() {
+    let mut game = game();
+    game.apply(Action::ToggleNote([0, 2], 1));
+    assert_eq!(game.cell([0, 2]).notes, 1 << 1);
+    game.set_settings(Settings {
+        auto_candidates: true,
+        ..Settings::default()
+    });
+    assert_eq!(game.candidates([0, 2]), 1 << 1 | 1 << 2 | 1 << 4);
+    game.apply(Action::ToggleNote([0, 2], 2));
+    assert_eq!(game.candidates([0, 2]), 1 << 1 | 1 << 4);
+    // digits which are not candidates cannot be removed
+    assert_eq!(game.apply(Action::ToggleNote([0, 2], 9)), Event::Ignored);
+}
+
+#[test]
+fn focus_skips_givens_and_wraps() {
+    let mut game = game();
+    assert_eq!(game.focus(), [0, 2]);
+    game.apply(Action::MoveFocus(Direction::Right));
+    assert_eq!(game.focus(), [0, 3]);
+    game.apply(Action::MoveFocus(Direction::Left));
+    game.apply(Action::MoveFocus(Direction::Left));
+    assert_eq!(game.focus(), [0, 8]);
+    game.apply(Action::MoveFocus(Direction::Next));
+    assert_eq!(game.focus(), [1, 1]);
+    game.apply(Action::MoveFocus(Direction::Prev));
+    assert_eq!(game.focus(), [0, 8]);
+    assert_eq!(game.apply(Action::SetFocus([0, 0])), Event::Ignored);
+    assert_eq!(game.focus(), [0, 8]);
+}
+
+#[test]
+fn notes_of_a_selection_change_in_one_step() {
+    let mut game = game();
+    game.apply(Action::SetFocus([0, 2]));
+    game.apply(Action::ExtendSelection(Direction::Right));
+    game.apply(Action::Select([0, 5]));
+    assert_eq!(game.selection(), [[0, 2], [0, 3], [0, 5]]);
+    assert_eq!(game.focus(), [0, 5]);
+
+    game.apply(Action::ToggleNote([0, 3], 4));
+    let cmd = game.notes_command(Some(4));
+    assert_eq!(cmd.to_string(), "r1c3 and 1 more: note +4");
+    assert_eq!(game.apply(Action::ToggleNotes(4)), Event::Changed);
+    let notes = |game: &Game| {
+        game.selection()
+            .iter()
+            .map(|&c| game.cell(c).notes)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(notes(&game), [1 << 4; 3]);
+    // marked everywhere, so toggling removes the mark
+    game.apply(Action::ToggleNotes(4));
+    assert_eq!(notes(&game), [0; 3]);
+    game.apply(Action::Undo);
+    assert_eq!(notes(&game), [1 << 4; 3]);
+    game.apply(Action::Undo);
+    assert_eq!(notes(&game), [0, 1 << 4, 0]);
+}
+
+#[test]
+fn colors_are_undoable_and_independent_of_digits() {
+    let mut game = game();
+    game.apply(Action::SetFocus([0, 2]));
+    game.apply(Action::Select([0, 3]));
+    assert_eq!(game.apply(Action::Color(2)), Event::Changed);
+    assert_eq!(game.cell([0, 2]).color, 2);
+    assert_eq!(game.cell([0, 3]).color, 2);
+
+    game.apply(Action::Fill([0, 2], 4));
+    game.apply(Action::Clear([0, 2]));
+    game.apply(Action::ToggleNote([0, 2], 1));
+    assert_eq!(game.cell([0, 2]).color, 2);
+
+    // both have it already, so it is removed
+    game.apply(Action::Select([0, 2]));
+    game.apply(Action::Color(2));
+    assert_eq!(game.cell([0, 3]).color, 0);
+    assert_eq!(game.cell([0, 2]).notes, 1 << 1);
+    game.apply(Action::Undo);
+    assert_eq!(game.cell([0, 3]).color, 2);
+    assert_eq!(game.apply(Action::Color(7)), Event::Ignored);
+}
+
+#[test]
+fn units_can_be_selected_and_cleared() {
+    let mut game = game();
+    game.apply(Action::SetFocus([0, 2]));
+    game.apply(Action::SelectUnit(Unit::Row(0)));
+    assert_eq!(game.selection().len(), 6);
+    assert_eq!(game.apply(Action::ClearNotes), Event::Ignored);
+    game.apply(Action::ToggleNotes(1));
+    game.apply(Action::ToggleNotes(2));
+    assert_eq!(game.apply(Action::ClearNotes), Event::Changed);
+    assert!(game.selection().iter().all(|&c| game.cell(c).notes == 0));
+    game.apply(Action::Undo);
+    assert!(game
+        .selection()
+        .iter()
+        .all(|&c| game.cell(c).notes == 1 << 1 | 1 << 2));
+
+    game.apply(Action::MoveFocus(Direction::Down));
+    assert_eq!(game.selection(), [game.focus()]);
+}
+
+#[test]
+fn editor_checks_conflicts_and_solutions() {
+    let mut editor = Editor::new();
+    assert_eq!(editor.solutions(), Solutions::Multiple);
+    editor.set([0, 0], 5);
+    editor.set([0, 8], 5);
+    assert_eq!(editor.conflicts(), vec![[0, 0], [0, 8]]);
+    assert_eq!(editor.solutions(), Solutions::None);
+    assert!(editor.play().is_err());
+    editor.set([0, 8], 0);
+    editor.set([9, 0], 1);
+    assert!(editor.conflicts().is_empty());
+    assert_eq!(editor.clues(), 1);
+}
+
+#[test]
+fn edited_puzzles_can_be_played() {
+    let mut editor = Editor::new();
+    for (x, c) in PUZZLE.chars().enumerate() {
+        editor.set([x / 9, x % 9], c.to_digit(10).unwrap_or(0) as u8);
+    }
+    assert_eq!(editor.solutions(), Solutions::Unique);
+    let mut game = editor.play().unwrap();
+    assert!(!game.is_available([0, 0]));
+    assert!(game.is_available([0, 2]));
+    assert_eq!(game.solution()[0][2], 4);
+    assert_eq!(game.apply(Action::Fill([0, 2], 4)), Event::Changed);
+}
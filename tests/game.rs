@@ -0,0 +1,121 @@
+use cursive::event::{Event, Key};
+use cursive::views::EditView;
+use cursive::Cursive;
+use sudoku_tui::board::SudokuBoard;
+use sudoku_tui::engine::{Action, Game};
+use sudoku_tui::game::add_board;
+use sudoku_tui::pack::Pack;
+use sudoku_tui::tabs::Tabs;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// A game of `PUZZLE` with 4 placed at r1c3 after a bookmark
+fn played() -> Game {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    game.apply(Action::Bookmark);
+    game.apply(Action::Fill([0, 2], 4));
+    game
+}
+
+fn siv_with(game: Game) -> Cursive {
+    let mut board = SudokuBoard::new();
+    board.resume_game(game);
+    let mut siv = Cursive::dummy();
+    add_board(&mut siv, board);
+    siv
+}
+
+fn board<T>(siv: &mut Cursive, f: impl FnOnce(&mut SudokuBoard) -> T) -> T {
+    siv.call_on_name("tabs", |tabs: &mut Tabs| f(tabs.board_mut()))
+        .unwrap()
+}
+
+fn r1c3(siv: &mut Cursive) -> u8 {
+    board(siv, |board| board.game().sudoku()[[0, 2]])
+}
+
+/// Type `path` into the text field `name` and press Enter.
+fn submit(siv: &mut Cursive, name: &str, path: &str) {
+    // fields only take Enter once laid out
+    siv.refresh();
+    siv.call_on_name(name, |v: &mut EditView| v.set_content(path))
+        .unwrap();
+    siv.on_event(Event::Key(Key::Enter));
+}
+
+#[test]
+fn jump_lists_the_bookmarks() {
+    let mut siv = siv_with(played());
+    siv.on_event(Event::Char('b'));
+    siv.on_event(Event::Key(Key::Enter));
+    assert_eq!(r1c3(&mut siv), 0);
+}
+
+#[test]
+fn tree_lists_the_branches() {
+    let mut game = played();
+    game.apply(Action::Undo);
+    let mut siv = siv_with(game);
+    siv.on_event(Event::Char('t'));
+    siv.on_event(Event::Key(Key::Enter));
+    assert_eq!(r1c3(&mut siv), 4);
+}
+
+#[test]
+fn save_and_export_write_the_board() {
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let saved = dir.join(format!("sudoku-tui-game-{}.json", id));
+    let sheet = dir.join(format!("sudoku-tui-game-{}.ps", id));
+
+    let mut siv = siv_with(played());
+    siv.on_event(Event::Char('s'));
+    submit(&mut siv, "save_path", saved.to_str().unwrap());
+    siv.pop_layer();
+    siv.on_event(Event::Char('x'));
+    submit(&mut siv, "export_path", sheet.to_str().unwrap());
+
+    let text = std::fs::read_to_string(&saved).unwrap();
+    assert!(text.contains(&PUZZLE[..9]));
+    assert!(std::fs::metadata(&sheet).unwrap().len() > 0);
+    let _ = std::fs::remove_file(saved);
+    let _ = std::fs::remove_file(sheet);
+}
+
+#[test]
+fn packs_opens_the_browser_once_a_pack_is_loaded() {
+    let mut siv = siv_with(played());
+    siv.on_event(Event::Char('p'));
+    assert!(siv.find_name::<EditView>("pack_path").is_some());
+    siv.pop_layer();
+
+    board(&mut siv, |board| {
+        board.load_pack(Pack::parse("Test", PUZZLE).unwrap())
+    });
+    siv.on_event(Event::Char('p'));
+    assert!(siv.find_name::<EditView>("pack_path").is_none());
+}
+
+#[test]
+fn leaving_the_editor_returns_to_the_game() {
+    let mut siv = siv_with(played());
+    let puzzle = board(&mut siv, |board| board.game().puzzle().clone());
+    board(&mut siv, SudokuBoard::edit);
+    for key in &['h', 'r', 'b', 't'] {
+        siv.on_event(Event::Char(*key));
+    }
+    assert_eq!(siv.screen().len(), 1);
+    board(&mut siv, |board| {
+        assert!(board.is_editing());
+        assert_eq!(board.game().hints(), 0);
+    });
+
+    siv.on_event(Event::Key(Key::Esc));
+    board(&mut siv, |board| {
+        assert!(!board.is_editing());
+        assert_eq!(board.game().puzzle(), &puzzle);
+    });
+    assert_eq!(r1c3(&mut siv), 4);
+}
@@ -0,0 +1,177 @@
+use sudoku_tui::engine::Game;
+use sudoku_tui::generator::{
+    solve_unique, Difficulty, Generator, Puzzle, Shape, Symmetry, Variant,
+};
+use sudoku_tui::solver::rate;
+
+/// Every row, column, box (and diagonal, for the diagonal variant) of the
+/// solution holds each digit once, and the givens agree with it.
+fn assert_valid(puzzle: &Puzzle) {
+    let n = puzzle.shape.size();
+    let (h, w) = (puzzle.shape.box_height, puzzle.shape.box_width);
+    let mut units: Vec<Vec<usize>> = Vec::new();
+    for a in 0..n {
+        units.push((0..n).map(|b| a * n + b).collect());
+        units.push((0..n).map(|b| b * n + a).collect());
+        units.push(
+            (0..n)
+                .map(|b| (a / h * h + b / w) * n + a % h * w + b % w)
+                .collect(),
+        );
+    }
+    if puzzle.variant == Variant::Diagonal {
+        units.push((0..n).map(|a| a * n + a).collect());
+        units.push((0..n).map(|a| a * n + n - 1 - a).collect());
+    }
+    for unit in units {
+        let mut digits: Vec<u8> = unit.iter().map(|&x| puzzle.solution[x]).collect();
+        digits.sort_unstable();
+        assert_eq!(digits, (1..=n as u8).collect::<Vec<_>>());
+    }
+    for (&g, &s) in puzzle.givens.iter().zip(&puzzle.solution) {
+        assert!(g == 0 || g == s);
+    }
+}
+
+#[test]
+fn classic_puzzles_have_a_unique_solution() {
+    for seed in 0..10 {
+        let puzzle = Generator::new().seed(seed).generate();
+        assert_valid(&puzzle);
+        let sudoku = puzzle.sudoku().unwrap();
+        assert_eq!(solve_unique(&sudoku), puzzle.solution_matrix());
+        assert!(puzzle.clues() >= 17);
+    }
+}
+
+#[test]
+fn seeds_are_deterministic() {
+    let a = Generator::new().seed(7).generate();
+    let b = Generator::new().seed(7).generate();
+    let c = Generator::new().seed(8).generate();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn clues_follow_the_requested_symmetry() {
+    let empty = |g: &[u8], i: usize, j: usize| g[i * 9 + j] == 0;
+    for &symmetry in &Symmetry::ALL {
+        for seed in 0..3 {
+            let puzzle = Generator::new().seed(seed).symmetry(symmetry).generate();
+            let g = &puzzle.givens;
+            for i in 0..9 {
+                for j in 0..9 {
+                    let e = empty(g, i, j);
+                    match symmetry {
+                        Symmetry::None => {}
+                        Symmetry::Rotational => assert_eq!(e, empty(g, 8 - i, 8 - j)),
+                        Symmetry::Horizontal => assert_eq!(e, empty(g, 8 - i, j)),
+                        Symmetry::Vertical => assert_eq!(e, empty(g, i, 8 - j)),
+                        Symmetry::Diagonal => assert_eq!(e, empty(g, j, i)),
+                        Symmetry::Dihedral => {
+                            assert_eq!(e, empty(g, j, i));
+                            assert_eq!(e, empty(g, 8 - i, j));
+                            assert_eq!(e, empty(g, 8 - j, 8 - i));
+                            assert_eq!(e, empty(g, j, 8 - i));
+                        }
+                    }
+                }
+            }
+            assert_eq!(
+                solve_unique(&puzzle.sudoku().unwrap()),
+                puzzle.solution_matrix()
+            );
+            if symmetry != Symmetry::None {
+                assert_eq!(Symmetry::detect(g), symmetry, "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn symmetries_are_named() {
+    for &symmetry in &Symmetry::ALL {
+        assert_eq!(symmetry.to_string().parse(), Ok(symmetry));
+    }
+    assert!("spiral".parse::<Symmetry>().is_err());
+}
+
+#[test]
+fn other_shapes_and_variants() {
+    for &(shape, variant) in &[
+        (Shape::new(2, 2).unwrap(), Variant::Classic),
+        (Shape::new(2, 3).unwrap(), Variant::Classic),
+        (Shape::new(3, 2).unwrap(), Variant::Classic),
+        (Shape::CLASSIC, Variant::Diagonal),
+        (Shape::new(2, 3).unwrap(), Variant::Diagonal),
+    ] {
+        let puzzle = Generator::new()
+            .seed(1)
+            .shape(shape)
+            .variant(variant)
+            .generate();
+        assert_eq!(puzzle.givens.len(), shape.cells());
+        assert_valid(&puzzle);
+        assert!(puzzle.sudoku().is_none() || shape == Shape::CLASSIC);
+    }
+}
+
+#[test]
+fn puzzles_match_the_target_difficulty() {
+    for &difficulty in &[Difficulty::Easy, Difficulty::Medium] {
+        let puzzle = Generator::new().seed(3).difficulty(difficulty).generate();
+        let rating = rate(&puzzle.sudoku().unwrap());
+        assert!(difficulty.contains(&rating), "{} {}", difficulty, rating);
+    }
+}
+
+#[test]
+fn puzzles_without_unique_solution_are_rejected() {
+    let empty = ".".repeat(81).parse().unwrap();
+    assert_eq!(solve_unique(&empty), None);
+    let clash = format!("11{}", ".".repeat(79)).parse().unwrap();
+    assert_eq!(solve_unique(&clash), None);
+}
+
+#[test]
+fn batches_do_not_depend_on_threads() {
+    use sudoku_tui::generator::batch;
+    let generator = Generator::new().symmetry(Symmetry::Vertical);
+    let mut done = Vec::new();
+    let one = batch(&generator, 5, 6, 1, |n| done.push(n));
+    assert_eq!(done, (1..=6).collect::<Vec<_>>());
+    let four = batch(&generator, 5, 6, 4, |_| {});
+    assert_eq!(one, four);
+    assert_ne!(one[0], one[1]);
+}
+
+#[test]
+fn options_are_parsed_by_name() {
+    assert_eq!("6x6".parse(), Ok(Shape::new(2, 3).unwrap()));
+    assert_eq!("4".parse(), Ok(Shape::new(2, 2).unwrap()));
+    assert!("6x4".parse::<Shape>().is_err());
+    for prime in &["1", "2", "3", "5", "7"] {
+        assert!(prime.parse::<Shape>().is_err());
+    }
+    assert_eq!("diagonal".parse(), Ok(Variant::Diagonal));
+    assert_eq!("hard".parse(), Ok(Difficulty::Hard));
+}
+
+#[test]
+fn shapes_larger_than_9x9_are_rejected() {
+    assert_eq!(Shape::new(3, 3), Some(Shape::CLASSIC));
+    assert_eq!(Shape::new(4, 4), None);
+    assert_eq!(Shape::new(0, 3), None);
+    assert_eq!(Shape::new(usize::MAX, 2), None);
+}
+
+#[test]
+fn only_classic_puzzles_make_games() {
+    let mut classic = Generator::new().seed(4);
+    assert!(Game::generate(&mut classic).is_some());
+    let mut diagonal = Generator::new().seed(4).variant(Variant::Diagonal);
+    assert!(Game::generate(&mut diagonal).is_none());
+    let mut small = Generator::new().seed(4).shape(Shape::new(2, 2).unwrap());
+    assert!(Game::generate(&mut small).is_none());
+}
@@ -0,0 +1,218 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sudoku_tui::engine::{Action, Event, Game, Settings};
+use sudoku_tui::history::{Command, UndoTree};
+use sudoku_tui::sudoku::Sudoku;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// A new game; with automatic candidates for odd seeds, so that the
+/// eliminations changed along with digits are undone too
+fn game(seed: u64) -> Game {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.set_settings(Settings {
+        auto_candidates: seed % 2 == 1,
+        ..Settings::default()
+    });
+    game
+}
+
+/// Any action a player can take, on any cell, givens included
+fn random_action(rng: &mut StdRng) -> Action {
+    let coord = [rng.gen_range(0, 9), rng.gen_range(0, 9)];
+    let v = rng.gen_range(1, 10);
+    match rng.gen_range(0, 12) {
+        0 | 1 => Action::Fill(coord, v),
+        2 => Action::Clear(coord),
+        3 => Action::ToggleNote(coord, v),
+        4 => Action::Hint,
+        5 => Action::Select(coord),
+        6 => Action::ToggleNotes(v),
+        7 => Action::Color(rng.gen_range(0, 7)),
+        8 => Action::Undo,
+        9 => Action::SelectBranch(rng.gen_range(0, 3)),
+        10 => Action::Redo,
+        _ => Action::Bookmark,
+    }
+}
+
+/// Undo until there is nothing left to undo, returning how many steps that
+/// took.
+fn undo_all(game: &mut Game) -> usize {
+    let mut n = 0;
+    while game.apply(Action::Undo) != Event::Ignored {
+        n += 1;
+    }
+    n
+}
+
+#[test]
+fn undo_everything_restores_the_puzzle() {
+    for seed in 0..200 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = game(seed);
+        for _ in 0..rng.gen_range(0, 100) {
+            game.apply(random_action(&mut rng));
+        }
+        let before = game.sudoku().clone();
+        let n = undo_all(&mut game);
+        assert_eq!(game.sudoku(), game.puzzle(), "seed {}", seed);
+        for _ in 0..n {
+            assert_ne!(game.apply(Action::Redo), Event::Ignored, "seed {}", seed);
+        }
+        assert_eq!(game.sudoku(), &before, "seed {}", seed);
+    }
+}
+
+#[test]
+fn undo_then_redo_is_identity() {
+    for seed in 0..200 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = game(seed);
+        for _ in 0..100 {
+            game.apply(random_action(&mut rng));
+            let before = game.sudoku().clone();
+            if game.apply(Action::Undo) != Event::Ignored {
+                assert_ne!(game.apply(Action::Redo), Event::Ignored);
+            }
+            assert_eq!(game.sudoku(), &before, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn jumping_to_a_bookmark_restores_its_board() {
+    for seed in 0..200 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = game(seed);
+        let mut snapshots: Vec<Sudoku> = Vec::new();
+        for _ in 0..100 {
+            let action = random_action(&mut rng);
+            game.apply(action);
+            if action == Action::Bookmark {
+                snapshots.push(game.sudoku().clone());
+            }
+        }
+        for _ in 0..snapshots.len() {
+            let k = rng.gen_range(0, snapshots.len());
+            game.apply(Action::JumpToBookmark(k));
+            assert_eq!(game.sudoku(), &snapshots[k], "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn hints_can_be_undone() {
+    let mut game = game(0);
+    game.apply(Action::Hint);
+    let x = (0..81)
+        .find(|&x| game.sudoku()[[x / 9, x % 9]] != game.puzzle()[[x / 9, x % 9]])
+        .unwrap();
+    let coord = [x / 9, x % 9];
+    assert!(!game.is_available(coord));
+    game.apply(Action::Undo);
+    assert_eq!(game.sudoku()[coord], 0);
+    assert!(game.is_available(coord));
+}
+
+#[test]
+fn undo_reverts_the_undone_cell() {
+    let mut game = game(0);
+    game.apply(Action::Fill([0, 2], 4));
+    game.apply(Action::Fill([1, 1], 7));
+    game.apply(Action::Undo);
+    game.apply(Action::Undo);
+    game.apply(Action::Redo);
+    assert_eq!(game.sudoku()[[0, 2]], 4);
+    assert_eq!(game.sudoku()[[1, 1]], 0);
+    game.apply(Action::Redo);
+    assert_eq!(game.sudoku()[[1, 1]], 7);
+}
+
+#[test]
+fn notes_are_preserved_when_filling_and_clearing() {
+    let mut game = game(0);
+    game.apply(Action::ToggleNote([0, 2], 1));
+    game.apply(Action::ToggleNote([0, 2], 4));
+    game.apply(Action::Fill([0, 2], 4));
+    game.apply(Action::Clear([0, 2]));
+    assert_eq!(game.sudoku().notes([0, 2]), vec![1, 4]);
+    game.apply(Action::Undo);
+    game.apply(Action::Undo);
+    game.apply(Action::Undo);
+    assert_eq!(game.sudoku().notes([0, 2]), vec![1]);
+}
+
+#[test]
+fn compound_commands_are_undone_as_one_step() {
+    let mut game = game(1);
+    // with automatic candidates, a pencil mark removes a candidate, which
+    // comes back once a peer holds the digit
+    game.apply(Action::ToggleNote([0, 3], 2));
+    assert_eq!(game.sudoku().eliminated[0][3], 1 << 2);
+    game.apply(Action::Fill([0, 2], 2));
+    assert_eq!(game.sudoku().eliminated[0][3], 0);
+    game.apply(Action::Undo);
+    assert_eq!(game.sudoku()[[0, 2]], 0);
+    assert_eq!(game.sudoku().eliminated[0][3], 1 << 2);
+}
+
+/// Filling the empty cell `coord` of the puzzle with `v`
+fn fill(coord: [usize; 2], v: u8) -> Command {
+    Command::fill(&PUZZLE.parse().unwrap(), coord, v)
+}
+
+#[test]
+fn moves_after_undoing_start_a_branch() {
+    let mut tree = UndoTree::new();
+    tree.push(fill([0, 2], 4));
+    assert_eq!(tree.undo(), Some(fill([0, 2], 4).inverse()));
+    tree.push(fill([0, 2], 6));
+    tree.undo();
+    assert_eq!(tree.branches(), vec![fill([0, 2], 4), fill([0, 2], 6)]);
+    // redo follows the latest branch unless told otherwise
+    assert_eq!(tree.active_branch(), 1);
+    tree.select_branch(0);
+    assert_eq!(tree.redo(), Some(fill([0, 2], 4)));
+    assert_eq!(tree.depth(), 1);
+}
+
+#[test]
+fn repeated_moves_reuse_their_branch() {
+    let mut tree = UndoTree::new();
+    tree.push(fill([0, 2], 4));
+    tree.undo();
+    tree.push(fill([0, 2], 4));
+    tree.undo();
+    assert_eq!(tree.branches().len(), 1);
+}
+
+#[test]
+fn out_of_range_branches_are_ignored() {
+    let mut tree = UndoTree::new();
+    tree.push(fill([0, 2], 4));
+    tree.undo();
+    tree.select_branch(3);
+    assert_eq!(tree.active_branch(), 0);
+    assert_eq!(tree.redo(), Some(fill([0, 2], 4)));
+    assert_eq!(tree.redo(), None);
+}
+
+#[test]
+fn jumping_to_a_bookmark_crosses_branches() {
+    let mut tree = UndoTree::new();
+    tree.push(fill([0, 2], 4));
+    tree.push(fill([0, 3], 6));
+    tree.bookmark("guess".to_owned());
+    tree.undo();
+    tree.undo();
+    tree.push(fill([0, 2], 1));
+    assert_eq!(
+        tree.jump_to_bookmark(0),
+        vec![fill([0, 2], 1).inverse(), fill([0, 2], 4), fill([0, 3], 6)]
+    );
+    assert_eq!(tree.depth(), 2);
+    assert_eq!(tree.bookmarks()[0].name, "guess");
+    assert_eq!(tree.undo(), Some(fill([0, 3], 6).inverse()));
+    assert!(tree.jump_to_bookmark(1).is_empty());
+}
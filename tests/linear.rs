@@ -0,0 +1,142 @@
+use sudoku_tui::engine::Game;
+use sudoku_tui::linear::{run, Linear};
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+fn linear() -> Linear {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    Linear::new(game)
+}
+
+#[test]
+fn cells_are_announced() {
+    let mut linear = linear();
+    assert_eq!(linear.intro()[1], "Row 1, column 3, empty");
+    assert_eq!(linear.command("r1c1"), ["Row 1, column 1, 5, given"]);
+    assert_eq!(linear.cursor(), [0, 0]);
+    assert_eq!(linear.command("right"), ["Row 1, column 2, 3, given"]);
+    assert_eq!(
+        linear.command("r1c4 n6"),
+        ["Row 1, column 4, empty, pencil marks 6"]
+    );
+    linear.command("n2");
+    assert_eq!(
+        linear.command("where"),
+        ["Row 1, column 4, empty, pencil marks 2 6"]
+    );
+    linear.command("auto");
+    assert_eq!(
+        linear.command("R3C1"),
+        ["Row 3, column 1, empty, candidates 1 2"]
+    );
+    assert_eq!(linear.command("up"), ["Row 2, column 1, 6, given"]);
+    linear.command("r1c1");
+    assert!(linear.command("up")[0].starts_with("Edge of the board"));
+}
+
+#[test]
+fn digits_are_entered_by_typing() {
+    let mut linear = linear();
+    assert_eq!(linear.command("r1c3=4"), ["Row 1, column 3, 4"]);
+    assert_eq!(linear.game().sudoku()[[0, 2]], 4);
+    assert_eq!(linear.command("0"), ["Row 1, column 3, empty"]);
+    assert_eq!(linear.command("=4"), ["Row 1, column 3, 4"]);
+    assert_eq!(linear.command("clear"), ["Row 1, column 3, empty"]);
+    assert_eq!(
+        linear.command("9"),
+        ["Cannot enter 9 at row 1, column 3: there is already a 9 at row 3, column 2, in box 1"]
+    );
+    assert_eq!(
+        linear.command("r1c4=5"),
+        ["Cannot enter 5 at row 1, column 4: there is already a 5 at row 1, column 1 and at row 2, column 6, in row 1 and box 2"]
+    );
+    assert_eq!(linear.command("r1c1=1"), ["Row 1, column 1 is given"]);
+}
+
+#[test]
+fn allowed_conflicts_are_read_as_errors() {
+    let mut linear = linear();
+    assert_eq!(linear.command("allow"), ["Conflicting digits allowed"]);
+    assert_eq!(
+        linear.command("r1c3=5"),
+        ["Row 1, column 3, 5, error in row 1 and box 1"]
+    );
+    assert_eq!(linear.command("4"), ["Row 1, column 3, 4"]);
+    assert_eq!(linear.command("allow"), ["Conflicting digits not allowed"]);
+}
+
+#[test]
+fn undo_and_hints_announce_the_changed_cell() {
+    let mut linear = linear();
+    assert_eq!(linear.command("undo"), ["Nothing to undo"]);
+    linear.command("r5c5=5");
+    linear.command("r1c1");
+    assert_eq!(linear.command("undo"), ["Undone. Row 5, column 5, empty"]);
+    assert_eq!(linear.cursor(), [4, 4]);
+    assert_eq!(linear.command("redo"), ["Redone. Row 5, column 5, 5"]);
+    let hint = linear.command("hint");
+    assert!(hint[0].starts_with("Hint. Row "), "{:?}", hint);
+}
+
+#[test]
+fn units_are_read_out() {
+    let mut linear = linear();
+    assert_eq!(
+        linear.command("row 1"),
+        ["Row 1: 5, 3, empty, empty, 7, empty, empty, empty, empty"]
+    );
+    assert_eq!(
+        linear.command("column9"),
+        ["Column 9: empty, empty, empty, 3, 1, 6, empty, 5, 9"]
+    );
+    assert_eq!(
+        linear.command("box 5"),
+        ["Box 5: empty, 6, empty, 8, empty, 3, empty, 2, empty"]
+    );
+    // the current row by default
+    linear.command("r2c2");
+    assert!(linear.command("row")[0].starts_with("Row 2: 6, empty"));
+    let board = linear.command("board");
+    assert_eq!(board.len(), 9);
+    assert!(board[8].starts_with("Row 9: "));
+    assert_eq!(linear.command("row 10"), ["There is no row 10"]);
+}
+
+#[test]
+fn solving_is_announced() {
+    let mut linear = linear();
+    let ans = linear.game().solution();
+    let mut last = Vec::new();
+    for x in 0..81 {
+        let [i, j] = [x / 9, x % 9];
+        if linear.game().is_available([i, j]) {
+            last = linear.command(&format!("r{}c{}={}", i + 1, j + 1, ans[i][j]));
+        }
+    }
+    assert!(last[1].starts_with("Solved! Time "), "{:?}", last);
+}
+
+#[test]
+fn runs_line_by_line() {
+    let game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    let mut output = Vec::new();
+    run(
+        game,
+        "r1c3=4\nbogus\nquit\nr1c4=6\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Sudoku, 51 empty cells. Type help for a list of commands."
+    );
+    assert_eq!(lines[2], "> Row 1, column 3, 4");
+    assert!(lines[3].starts_with("> Unknown command 'bogus'"));
+    assert_eq!(lines[4], "> Goodbye");
+    // nothing is read after quitting
+    assert_eq!(lines.len(), 5);
+}
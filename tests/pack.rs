@@ -0,0 +1,119 @@
+use cursive::event::{Event, Key};
+use cursive::view::View;
+use std::time::Duration;
+use sudoku_tui::board::SudokuBoard;
+use sudoku_tui::pack::{Pack, PackEntry, PackError, Progress};
+
+const EASY: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+const SWORDFISH: &str =
+    "..9748...7.........2.1.9.....7...24..64.1.59..98...3.....8.3.2.........6...2759..";
+
+#[test]
+fn entries_have_optional_rating_and_name() {
+    let entry = PackEntry::parse(&format!("{} 1.2 The Classic", EASY))
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.puzzle.to_line(), EASY);
+    assert_eq!(entry.rating, Some(1.2));
+    assert_eq!(entry.name.as_deref(), Some("The Classic"));
+
+    let entry = PackEntry::parse(&format!("{}  Named only", EASY))
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.rating, None);
+    assert_eq!(entry.name.as_deref(), Some("Named only"));
+
+    let entry = PackEntry::parse(EASY).unwrap().unwrap();
+    assert_eq!(entry.rating, None);
+    assert_eq!(entry.name, None);
+}
+
+#[test]
+fn packs_skip_comments_and_report_bad_lines() {
+    let text = format!("# my pack\n\n{}\n{} 3.8\n", EASY, SWORDFISH);
+    let pack = Pack::parse("mine", &text).unwrap();
+    assert_eq!(pack.entries.len(), 2);
+    assert_eq!(pack.title(1), "mine #2");
+
+    let text = format!("{}\n{}x\n", EASY, SWORDFISH);
+    match Pack::parse("bad", &text) {
+        Err(PackError::Parse(2, _)) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(
+        Pack::parse("empty", "# nothing"),
+        Err(PackError::Empty)
+    ));
+}
+
+#[test]
+fn progress_keeps_best_time_across_loads() {
+    let path = std::env::temp_dir().join(format!("sudoku-tui-progress-{}.txt", std::process::id()));
+    let puzzle = EASY.parse().unwrap();
+    let mut progress = Progress::load_from(path.clone());
+    assert!(!progress.completed(&puzzle));
+    progress.record(&puzzle, Duration::from_secs(300)).unwrap();
+    progress.record(&puzzle, Duration::from_secs(200)).unwrap();
+    progress.record(&puzzle, Duration::from_secs(400)).unwrap();
+
+    let progress = Progress::load_from(path.clone());
+    assert_eq!(progress.best(&puzzle), Some(Duration::from_secs(200)));
+    assert!(!progress.completed(&SWORDFISH.parse().unwrap()));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn puzzles_without_a_unique_solution_are_skipped() {
+    let ambiguous = format!("1{}", ".".repeat(80));
+    let mut board = SudokuBoard::new();
+    board.load_pack(Pack::parse("Test", &format!("{}\n{}", ambiguous, EASY)).unwrap());
+    assert_eq!(board.game().sudoku().to_line(), EASY);
+
+    board.load_pack(Pack::parse("Test", &ambiguous).unwrap());
+    assert!(board.pack().is_none());
+}
+
+/// The solution of `EASY` with r1c3 (4) and r5c5 (5) left to fill in
+const ALMOST: &str =
+    "53.6789126721953481983425678597614234268.3791713924856961537284287419635345286179";
+
+/// A board playing `ALMOST`, from a pack of its own
+fn almost_solved() -> SudokuBoard {
+    let mut board = SudokuBoard::new();
+    board.load_pack(Pack::parse("Test", ALMOST).unwrap());
+    board.on_event(Event::Key(Key::Enter));
+    board
+}
+
+fn press(board: &mut SudokuBoard, event: Event) {
+    board.on_event(event);
+}
+
+#[test]
+fn only_solves_without_help_are_recorded() {
+    let puzzle = ALMOST.parse().unwrap();
+    let solve = [Event::Char('4'), Event::Key(Key::Tab), Event::Char('5')];
+
+    let mut board = almost_solved();
+    press(&mut board, Event::Char('h'));
+    press(&mut board, Event::Char('h'));
+    assert!(board.game().sudoku().finished());
+    assert!(!board.progress().completed(&puzzle));
+
+    let mut board = almost_solved();
+    press(&mut board, Event::Char('4'));
+    press(&mut board, Event::CtrlChar('z'));
+    for event in &solve {
+        press(&mut board, event.clone());
+    }
+    assert!(board.game().sudoku().finished());
+    assert!(!board.progress().completed(&puzzle));
+
+    let mut board = almost_solved();
+    for event in &solve {
+        press(&mut board, event.clone());
+    }
+    assert!(board.game().sudoku().finished());
+    assert!(board.progress().completed(&puzzle));
+}
@@ -0,0 +1,115 @@
+use sudoku_tui::generator::{Generator, Shape};
+use sudoku_tui::print::{Format, Item, Sheet};
+
+fn sheet(count: usize) -> Sheet {
+    let items = (0..count as u64).map(|seed| {
+        let puzzle = Generator::new().seed(seed).generate();
+        Item::new(format!("Puzzle {}", seed + 1), puzzle)
+    });
+    Sheet::new("Test").items(items)
+}
+
+/// Byte offset of `needle` in `haystack`, after `from`
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|k| k + from)
+}
+
+#[test]
+fn puzzles_are_split_into_pages() {
+    assert_eq!(sheet(7).per_page(6).pages(), 2);
+    assert_eq!(sheet(7).per_page(6).solutions(true).pages(), 4);
+    assert_eq!(sheet(4).per_page(4).pages(), 1);
+    assert_eq!(sheet(3).per_page(10).pages(), 1);
+    assert_eq!(sheet(3).per_page(0).pages(), 3);
+    assert_eq!(sheet(0).solutions(true).pages(), 1);
+}
+
+#[test]
+fn pdf_cross_references_point_to_objects() {
+    let pdf = sheet(3).per_page(2).solutions(true).render(Format::Pdf);
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+    assert!(find(&pdf, b"/Count 4 >>", 0).is_some());
+
+    let text = String::from_utf8(pdf.clone()).unwrap();
+    let start: usize = text
+        .rsplit("startxref\n")
+        .next()
+        .and_then(|s| s.lines().next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let mut lines = text[start..].lines();
+    assert_eq!(lines.next(), Some("xref"));
+    let size: usize = lines.next().unwrap()[2..].parse().unwrap();
+    lines.next();
+    for k in 1..size {
+        let offset: usize = lines.next().unwrap()[..10].parse().unwrap();
+        assert!(text[offset..].starts_with(&format!("{} 0 obj\n", k)));
+    }
+
+    // stream lengths are exact
+    let mut from = 0;
+    while let Some(k) = find(&pdf, b"/Length ", from) {
+        let end = find(&pdf, b" >>\nstream\n", k).unwrap();
+        let length: usize = text[k + 8..end].parse().unwrap();
+        let data = end + 11;
+        assert_eq!(&pdf[data + length..data + length + 9], b"endstream");
+        from = data;
+    }
+}
+
+#[test]
+fn solutions_fill_the_empty_cells() {
+    let puzzle = Generator::new().seed(4).generate();
+    let clues = puzzle.clues();
+    let ps = Sheet::new("Test")
+        .solutions(true)
+        .item(Item::new("Puzzle", puzzle))
+        .render(Format::PostScript);
+    let ps = String::from_utf8(ps).unwrap();
+    assert!(ps.starts_with("%!PS-Adobe-3.0\n"));
+    assert!(ps.contains("%%Pages: 2\n"));
+    assert_eq!(ps.matches("showpage").count(), 2);
+
+    let digits = |font: &str| {
+        ps.lines()
+            .filter(|l| l.len() > 3 && l.as_bytes()[1].is_ascii_digit() && l.ends_with(font))
+            .count()
+    };
+    // givens on both pages, the rest of the solution on the second
+    assert_eq!(digits(" /Helvetica-Bold T"), 2 * clues);
+    assert_eq!(digits(" /Helvetica T"), 81 - clues);
+}
+
+#[test]
+fn other_shapes_are_printed() {
+    let puzzle = Generator::new()
+        .seed(2)
+        .shape(Shape::new(2, 3).unwrap())
+        .generate();
+    let item = Item::new("Small", puzzle);
+    assert!(item.subtitle.starts_with("6x6"));
+    let ps = Sheet::new("Test").item(item).render(Format::PostScript);
+    // 7 horizontal and 7 vertical lines
+    let lines = String::from_utf8(ps).unwrap();
+    assert_eq!(lines.lines().filter(|l| l.ends_with(" L")).count(), 14);
+}
+
+#[test]
+fn text_is_escaped() {
+    let ps = Sheet::new(r"Tricky (1) \ café").render(Format::PostScript);
+    let ps = String::from_utf8(ps).unwrap();
+    assert!(ps.contains(r"(Tricky \(1\) \\ caf?)"));
+}
+
+#[test]
+fn formats_follow_extensions() {
+    assert_eq!(Format::from_path("a/sheet.pdf"), Some(Format::Pdf));
+    assert_eq!(Format::from_path("sheet.PS"), Some(Format::PostScript));
+    assert_eq!(Format::from_path("sheet.txt"), None);
+    assert_eq!("postscript".parse(), Ok(Format::PostScript));
+}
@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+use sudoku_tui::engine::{Action, Game};
+use sudoku_tui::generator::Generator;
+use sudoku_tui::net::lock;
+use sudoku_tui::race::{Host, Racer, Standing};
+
+/// Wait for the standings seen by `racer` to satisfy `done`.
+fn wait_for(racer: &Racer, done: impl Fn(&[Standing]) -> bool) -> Vec<Standing> {
+    let start = Instant::now();
+    loop {
+        let standings = racer.standings();
+        if done(&standings) {
+            return standings;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "standings: {:?}",
+            standings
+        );
+        sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn racers_get_the_seeded_puzzle() {
+    let host = Host::bind("127.0.0.1:0", 42).unwrap();
+    let alice = Racer::join(host.local_addr(), "Alice").unwrap();
+    let bob = Racer::join(host.local_addr(), "Bob").unwrap();
+    assert_eq!((alice.id(), bob.id()), (0, 1));
+    assert_eq!(bob.seed(), 42);
+    let puzzle = Generator::new().seed(42).generate();
+    assert_eq!(puzzle.sudoku().unwrap(), *alice.puzzle());
+    assert_eq!(alice.puzzle(), bob.puzzle());
+    assert_eq!(alice.cells(), 81 - puzzle.clues());
+
+    let standings = wait_for(&alice, |s| s.len() == 2);
+    assert_eq!(standings[1].name, "Bob");
+    assert_eq!(standings[1].filled, 0);
+}
+
+#[test]
+fn progress_reaches_the_other_racers() {
+    let host = Host::bind("127.0.0.1:0", 7).unwrap();
+    let mut alice = Racer::join(host.local_addr(), "Alice").unwrap();
+    let bob = Racer::join(host.local_addr(), "Bob").unwrap();
+    let mut game = Game::from_puzzle(alice.puzzle()).unwrap();
+    game.start();
+
+    let ans = game.solution();
+    let empty: Vec<[usize; 2]> = (0..81)
+        .map(|x| [x / 9, x % 9])
+        .filter(|&c| game.is_available(c))
+        .collect();
+    for &[i, j] in &empty[..3] {
+        game.apply(Action::Fill([i, j], ans[i][j]));
+    }
+    alice.mistake();
+    alice.update(&game).unwrap();
+    let standings = wait_for(&bob, |s| s.len() == 2 && s[0].filled == 3);
+    assert_eq!(standings[0].mistakes, 1);
+    assert_eq!(standings[0].finished_ms, None);
+
+    alice.finish(Duration::from_millis(61_500)).unwrap();
+    let standings = wait_for(&bob, |s| s[0].finished_ms.is_some());
+    assert_eq!(
+        standings[0].finish_time(),
+        Some(Duration::from_millis(61_500))
+    );
+}
+
+#[test]
+fn leaving_is_reported() {
+    let host = Host::bind("127.0.0.1:0", 3).unwrap();
+    let alice = Racer::join(host.local_addr(), "Alice").unwrap();
+    let bob = Racer::join(host.local_addr(), "Bob").unwrap();
+    wait_for(&alice, |s| s.len() == 2);
+    drop(bob);
+    let standings = wait_for(&alice, |s| s[1].left);
+    assert!(!standings[0].left);
+    assert!(alice.is_connected());
+}
+
+#[test]
+fn locks_survive_a_panicking_thread() {
+    let shared = Arc::new(Mutex::new(1));
+    let other = Arc::clone(&shared);
+    let _ = thread::spawn(move || {
+        let _guard = lock(&other);
+        panic!("connection thread failed");
+    })
+    .join();
+    assert!(shared.is_poisoned());
+    *lock(&shared) += 1;
+    assert_eq!(*lock(&shared), 2);
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+use sudoku_tui::engine::{Action, Game};
+use sudoku_tui::generator::Symmetry;
+use sudoku_tui::history::UndoTree;
+use sudoku_tui::save::{SaveError, SavedGame, VERSION};
+use sudoku_tui::sudoku::Sudoku;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+const SOLUTION: &str =
+    "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+/// A game with entries, notes, a bookmark and two branches in its history
+fn played() -> Game {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    game.apply(Action::Fill([0, 2], 4));
+    game.apply(Action::Bookmark);
+    game.apply(Action::ToggleNote([0, 3], 6));
+    game.apply(Action::ToggleNote([0, 3], 2));
+    game.apply(Action::Undo);
+    game.apply(Action::Fill([0, 3], 6));
+    game.apply(Action::SetFocus([4, 4]));
+    game
+}
+
+#[test]
+fn games_survive_a_round_trip() {
+    let game = played();
+    let json = game.to_saved(Some("morning".to_owned())).to_json();
+    let saved = SavedGame::from_json(&json).unwrap();
+    assert_eq!(saved.version, VERSION);
+    assert_eq!(saved.metadata.name.as_deref(), Some("morning"));
+    assert_eq!(saved.solution, SOLUTION);
+    let elapsed = Duration::from_millis(saved.elapsed_ms);
+
+    let mut loaded = Game::from_saved(saved).unwrap();
+    assert_eq!(loaded.sudoku(), game.sudoku());
+    assert_eq!(loaded.puzzle(), game.puzzle());
+    assert_eq!(loaded.focus(), [4, 4]);
+    assert_eq!(loaded.moves(), game.moves());
+    assert_eq!(loaded.undos(), 1);
+    // the timer stays paused until the game is resumed
+    assert_eq!(loaded.elapsed(), elapsed);
+    assert_eq!(loaded.history().bookmarks(), game.history().bookmarks());
+
+    // the abandoned note is still in the history
+    loaded.apply(Action::Undo);
+    assert_eq!(loaded.history().branches().len(), 2);
+    assert_eq!(loaded.cell([0, 3]).notes, 1 << 6);
+    loaded.apply(Action::JumpToBookmark(0));
+    assert_eq!(loaded.cell([0, 3]).notes, 0);
+    assert_eq!(loaded.cell([0, 2]).value, 4);
+}
+
+#[test]
+fn colors_survive_a_round_trip() {
+    let mut game = played();
+    game.apply(Action::SetFocus([0, 2]));
+    game.apply(Action::Color(3));
+    let json = game.to_saved(None).to_json();
+    let mut loaded = Game::from_saved(SavedGame::from_json(&json).unwrap()).unwrap();
+    assert_eq!(loaded.cell([0, 2]).color, 3);
+    loaded.apply(Action::Undo);
+    assert_eq!(loaded.cell([0, 2]).color, 0);
+}
+
+#[test]
+fn boards_serialize_as_lines() {
+    let mut sudoku: Sudoku = PUZZLE.parse().unwrap();
+    sudoku.set([0, 2], 4);
+    sudoku.notes[0][3] = 0b1000100;
+    let json = serde_json::to_value(&sudoku).unwrap();
+    assert_eq!(json["givens"], PUZZLE);
+    assert_eq!(json["entries"].as_str().unwrap()[..4], *"..4.");
+    let back: Sudoku = serde_json::from_value(json).unwrap();
+    assert_eq!(back, sudoku);
+}
+
+#[test]
+fn colors_are_written_only_when_used() {
+    let mut sudoku: Sudoku = PUZZLE.parse().unwrap();
+    assert!(serde_json::to_value(&sudoku)
+        .unwrap()
+        .get("colors")
+        .is_none());
+    sudoku.colors[1][1] = 2;
+    let mut json = serde_json::to_value(&sudoku).unwrap();
+    assert_eq!(json["colors"][10], 2);
+    let back: Sudoku = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(back, sudoku);
+
+    json["colors"][10] = 7.into();
+    assert!(serde_json::from_value::<Sudoku>(json).is_err());
+}
+
+#[test]
+fn undo_trees_survive_a_round_trip() {
+    let game = played();
+    let json = serde_json::to_string(game.history()).unwrap();
+    let tree: UndoTree = serde_json::from_str(&json).unwrap();
+    assert_eq!(tree.depth(), game.history().depth());
+    assert_eq!(tree.branches(), game.history().branches());
+    assert_eq!(tree.bookmarks(), game.history().bookmarks());
+}
+
+#[test]
+fn generated_puzzles_are_migrated() {
+    let json = format!(
+        r#"{{"puzzle": "{}", "rating": 1.2, "hardest": "Hidden Single"}}"#,
+        PUZZLE
+    );
+    let saved = SavedGame::from_json(&json).unwrap();
+    assert_eq!(saved.version, VERSION);
+    assert_eq!(saved.solution, SOLUTION);
+    assert_eq!(saved.metadata.rating, Some(1.2));
+    assert_eq!(
+        saved.metadata.symmetry,
+        Symmetry::detect(&saved.board.matrix().concat())
+    );
+    assert_eq!(saved.focus, [0, 2]);
+    let game = Game::from_saved(saved).unwrap();
+    assert_eq!(game.moves(), 0);
+    assert_eq!(game.history().depth(), 0);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut value = serde_json::to_value(played().to_saved(None)).unwrap();
+    value["version"] = (VERSION + 1).into();
+    match SavedGame::from_json(&value.to_string()) {
+        Err(SaveError::Version(v)) => assert_eq!(v, VERSION + 1),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn inconsistent_games_are_rejected() {
+    let mut saved = played().to_saved(None);
+    saved.solution = SOLUTION.replace("534", "435");
+    assert!(matches!(
+        Game::from_saved(saved),
+        Err(SaveError::Invalid(_))
+    ));
+
+    let mut saved = played().to_saved(None);
+    saved.board.set([0, 0], 1);
+    assert!(Game::from_saved(saved).is_err());
+
+    let mut value = serde_json::to_value(played().to_saved(None)).unwrap();
+    value["board"]["entries"] = "5".repeat(81).into();
+    assert!(SavedGame::from_json(&value.to_string()).is_err());
+}
+
+#[test]
+fn generated_batches_load_their_first_puzzle() {
+    let json = format!(
+        r#"{{"seed": 7, "difficulty": null, "puzzles": [{{"puzzle": "{}", "solution": "{}"}}]}}"#,
+        PUZZLE, SOLUTION
+    );
+    let saved = SavedGame::from_json(&json).unwrap();
+    assert_eq!(saved.puzzle, PUZZLE);
+    assert!(Game::from_saved(saved).is_ok());
+    assert!(SavedGame::from_json(r#"{"seed": 7, "puzzles": []}"#).is_err());
+}
+
+#[test]
+fn corrupted_histories_are_rejected() {
+    let path =
+        std::env::temp_dir().join(format!("sudoku-tui-corrupted-{}.json", std::process::id()));
+    let load = |value: &serde_json::Value| {
+        std::fs::write(&path, value.to_string()).unwrap();
+        SavedGame::load(&path).and_then(Game::from_saved)
+    };
+    let value = serde_json::to_value(played().to_saved(None)).unwrap();
+    assert!(load(&value).is_ok());
+
+    let mut off_board = value.clone();
+    off_board["history"]["moves"][0]["changes"][0]["coord"] = serde_json::json!([9, 9]);
+    assert!(load(&off_board).is_err());
+
+    let mut bad_digit = value.clone();
+    bad_digit["history"]["moves"][0]["changes"][0]["after"]["value"] = 12.into();
+    assert!(load(&bad_digit).is_err());
+
+    let mut empty = value.clone();
+    empty["history"]["moves"][0]["changes"] = serde_json::json!([]);
+    assert!(load(&empty).is_err());
+
+    let mut given = value;
+    given["history"]["moves"][0]["changes"][0]["coord"] = serde_json::json!([0, 0]);
+    assert!(matches!(load(&given), Err(SaveError::Invalid(_))));
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,70 @@
+use sudoku_tui::solver::{rate, Solver, Technique};
+use sudoku_tui::sudoku::Sudoku;
+
+const EASY: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+const SWORDFISH: &str =
+    "..9748...7.........2.1.9.....7...24..64.1.59..98...3.....8.3.2.........6...2759..";
+const HARD: &str =
+    "1......7..5.9.....4..3..9......4.5..8..6...1..2.......7...8......1.3.2...6...5.4.";
+
+fn is_valid_solution(values: [[u8; 9]; 9]) -> bool {
+    let sudoku = Sudoku::from_matrix(values);
+    (0..81).all(|x| {
+        let coord = [x / 9, x % 9];
+        let v = sudoku[coord];
+        v != 0 && Sudoku::peers(coord).all(|p| sudoku[p] != v)
+    })
+}
+
+#[test]
+fn easy_puzzle_needs_only_singles() {
+    let sudoku: Sudoku = EASY.parse().unwrap();
+    let rating = rate(&sudoku);
+    assert!(rating.solved);
+    assert!(rating.hardest.unwrap() <= Technique::NakedSingle);
+    assert_eq!(rating.steps, EASY.matches('.').count());
+}
+
+#[test]
+fn solution_is_valid_and_keeps_givens() {
+    for puzzle in [EASY, SWORDFISH].iter() {
+        let sudoku: Sudoku = puzzle.parse().unwrap();
+        let mut solver = Solver::new(&sudoku);
+        solver.solve();
+        assert!(solver.is_solved());
+        let values = solver.values();
+        assert!(is_valid_solution(values));
+        for x in 0..81 {
+            let v = sudoku[[x / 9, x % 9]];
+            assert!(v == 0 || values[x / 9][x % 9] == v);
+        }
+    }
+}
+
+#[test]
+fn rating_is_hardest_technique() {
+    let sudoku: Sudoku = SWORDFISH.parse().unwrap();
+    let rating = rate(&sudoku);
+    assert!(rating.solved);
+    assert_eq!(rating.hardest, Some(Technique::Swordfish));
+    assert!((rating.rating - 3.8).abs() < 1e-6);
+}
+
+#[test]
+fn hard_puzzles_are_reported_unsolved() {
+    let sudoku: Sudoku = HARD.parse().unwrap();
+    let rating = rate(&sudoku);
+    assert!(!rating.solved);
+    assert!(rating.to_string().starts_with('>'));
+}
+
+#[test]
+fn solved_grid_has_zero_rating() {
+    let mut solver = Solver::new(&EASY.parse().unwrap());
+    solver.solve();
+    let rating = rate(&Sudoku::from_matrix(solver.values()));
+    assert!(rating.solved);
+    assert_eq!(rating.steps, 0);
+    assert_eq!(rating.hardest, None);
+}
@@ -0,0 +1,164 @@
+use sudoku_tui::sudoku::{Sudoku, Unit};
+
+const PUZZLE: [[u8; 9]; 9] = [
+    [5, 3, 0, 0, 7, 0, 0, 0, 0],
+    [6, 0, 0, 1, 9, 5, 0, 0, 0],
+    [0, 9, 8, 0, 0, 0, 0, 6, 0],
+    [8, 0, 0, 0, 6, 0, 0, 0, 3],
+    [4, 0, 0, 8, 0, 3, 0, 0, 1],
+    [7, 0, 0, 0, 2, 0, 0, 0, 6],
+    [0, 6, 0, 0, 0, 0, 2, 8, 0],
+    [0, 0, 0, 4, 1, 9, 0, 0, 5],
+    [0, 0, 0, 0, 8, 0, 0, 7, 9],
+];
+
+fn digits(mask: u16) -> Vec<u8> {
+    (1..=9).filter(|v| mask & (1 << v) != 0).collect()
+}
+
+#[test]
+fn every_cell_has_twenty_peers() {
+    for x in 0..81 {
+        let coord = [x / 9, x % 9];
+        let mut peers: Vec<_> = Sudoku::peers(coord).collect();
+        assert_eq!(peers.len(), 20);
+        peers.sort_unstable();
+        peers.dedup();
+        assert_eq!(peers.len(), 20);
+        assert!(!peers.contains(&coord));
+    }
+}
+
+#[test]
+fn candidates_follow_placed_digits() {
+    let mut sudoku = Sudoku::from_matrix(PUZZLE);
+    assert_eq!(digits(sudoku.candidates([0, 2])), vec![1, 2, 4]);
+    assert_eq!(sudoku.candidates([0, 0]), 0);
+    sudoku.set([1, 1], 2);
+    assert_eq!(digits(sudoku.candidates([0, 2])), vec![1, 4]);
+    sudoku.set([1, 1], 0);
+    assert_eq!(digits(sudoku.candidates([0, 2])), vec![1, 2, 4]);
+}
+
+#[test]
+fn parses_and_prints_lines() {
+    let line = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    let sudoku: Sudoku = line.parse().unwrap();
+    assert_eq!(sudoku, Sudoku::from_matrix(PUZZLE));
+    assert_eq!(sudoku.to_line(), line);
+    let zeros: Sudoku = line.replace('.', "0").parse().unwrap();
+    assert_eq!(zeros, sudoku);
+}
+
+#[test]
+fn rejects_malformed_lines() {
+    use sudoku_tui::sudoku::ParseSudokuError;
+    assert_eq!(
+        "123".parse::<Sudoku>().unwrap_err(),
+        ParseSudokuError::Length(3)
+    );
+    let bad = format!("x{}", ".".repeat(80));
+    assert_eq!(
+        bad.parse::<Sudoku>().unwrap_err(),
+        ParseSudokuError::InvalidChar('x')
+    );
+}
+
+/// The digit in the same row, column or box as `coord`, found by scanning.
+fn scan_conflict(m: &[[u8; 9]; 9], v: u8, [i, j]: [usize; 2]) -> Option<[usize; 2]> {
+    let row = (0..9).map(|x| [i, x]);
+    let col = (0..9).map(|x| [x, j]);
+    let box_ = (0..9).map(|x| [i / 3 * 3 + x / 3, j / 3 * 3 + x % 3]);
+    row.chain(col).chain(box_).find(|&[a, b]| m[a][b] == v)
+}
+
+#[test]
+fn unit_masks_agree_with_scanning() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sudoku = Sudoku::from_matrix(PUZZLE);
+        // digits are written without checking, so units may hold duplicates
+        for _ in 0..200 {
+            let coord = [rng.gen_range(0, 9), rng.gen_range(0, 9)];
+            sudoku.set(coord, rng.gen_range(0, 10));
+            let m = sudoku.matrix();
+            let empty = m.iter().flatten().filter(|&&v| v == 0).count();
+            let errors: Vec<[usize; 2]> = (0..81)
+                .map(|x| [x / 9, x % 9])
+                .filter(|&c| m[c[0]][c[1]] != 0)
+                .filter(|&c| !sudoku.conflicts(m[c[0]][c[1]], c).is_empty())
+                .collect();
+            assert_eq!(sudoku.errors(), errors, "seed {}", seed);
+            let valid = errors.is_empty();
+            assert_eq!(sudoku.is_full(), empty == 0, "seed {}", seed);
+            assert_eq!(sudoku.is_valid(), valid, "seed {}", seed);
+            assert_eq!(sudoku.finished(), empty == 0 && valid, "seed {}", seed);
+            for x in 0..81 {
+                let c = [x / 9, x % 9];
+                let v = rng.gen_range(1, 10);
+                assert_eq!(sudoku.conflict(v, c), scan_conflict(&m, v, c));
+                let clashes: Vec<[usize; 2]> = (0..81)
+                    .map(|y| [y / 9, y % 9])
+                    .filter(|&p| p != c && m[p[0]][p[1]] == v)
+                    .filter(|p| {
+                        p[0] == c[0] || p[1] == c[1] || (p[0] / 3, p[1] / 3) == (c[0] / 3, c[1] / 3)
+                    })
+                    .collect();
+                assert_eq!(sudoku.conflicts(v, c), clashes);
+                let expected = if m[c[0]][c[1]] == 0 {
+                    (1..=9)
+                        .filter(|&v| scan_conflict(&m, v, c).is_none())
+                        .fold(0, |mask, v| mask | 1 << v)
+                } else {
+                    0
+                };
+                assert_eq!(sudoku.candidates(c), expected, "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn filling_the_last_cell_finishes() {
+    let line = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+    let mut sudoku: Sudoku = line.parse().unwrap();
+    assert!(sudoku.finished());
+    sudoku.set([4, 4], 0);
+    assert!(!sudoku.finished());
+    assert_eq!(digits(sudoku.candidates([4, 4])), vec![5]);
+    sudoku.set([4, 4], 5);
+    assert!(sudoku.finished());
+}
+
+#[test]
+fn digits_are_counted() {
+    let line = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+    let mut sudoku: Sudoku = line.parse().unwrap();
+    assert!((1..=9).all(|v| sudoku.count(v) == 9));
+    sudoku.set([4, 4], 0);
+    sudoku.set([0, 2], 0);
+    assert_eq!(sudoku.count(5), 8);
+    assert_eq!(sudoku.count(4), 8);
+    sudoku.set([4, 4], 4);
+    assert_eq!(sudoku.count(4), 9);
+    assert_eq!(sudoku.count(5), 8);
+}
+
+#[test]
+fn units_hold_their_cells() {
+    let [row, column, box_] = Unit::of([4, 7]);
+    assert_eq!(
+        (row, column, box_),
+        (Unit::Row(4), Unit::Column(7), Unit::Box(5))
+    );
+    for unit in &[row, column, box_] {
+        let cells: Vec<_> = unit.cells().collect();
+        assert_eq!(cells.len(), 9);
+        assert!(cells.contains(&[4, 7]));
+        assert!(cells.iter().all(|&c| unit.contains(c)));
+    }
+    assert_eq!(box_.cells().next(), Some([3, 6]));
+    assert!(!box_.contains([4, 5]));
+    assert_eq!(box_.to_string(), "box 6");
+}
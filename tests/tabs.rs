@@ -0,0 +1,65 @@
+use std::thread::sleep;
+use std::time::Duration;
+use sudoku_tui::board::SudokuBoard;
+use sudoku_tui::engine::Game;
+use sudoku_tui::tabs::Tabs;
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+fn board() -> SudokuBoard {
+    let puzzle = PUZZLE.parse().unwrap();
+    let mut board = SudokuBoard::from_puzzle(&puzzle).unwrap();
+    let mut game = Game::from_puzzle(&puzzle).unwrap();
+    game.start();
+    board.resume_game(game);
+    board
+}
+
+#[test]
+fn hidden_games_are_paused() {
+    let mut tabs = Tabs::new(board());
+    tabs.open(board());
+    assert_eq!(tabs.active(), 1);
+
+    let hidden = tabs.boards()[0].game().elapsed();
+    sleep(Duration::from_millis(20));
+    assert_eq!(tabs.boards()[0].game().elapsed(), hidden);
+    assert!(tabs.board().game().elapsed() > Duration::from_millis(10));
+
+    tabs.cycle(true);
+    assert_eq!(tabs.active(), 0);
+    let shown = tabs.board().game().elapsed();
+    let hidden = tabs.boards()[1].game().elapsed();
+    sleep(Duration::from_millis(20));
+    assert!(tabs.board().game().elapsed() > shown);
+    assert_eq!(tabs.boards()[1].game().elapsed(), hidden);
+}
+
+#[test]
+fn games_have_their_own_history() {
+    let mut tabs = Tabs::new(board());
+    tabs.board_mut().hint();
+    tabs.open(board());
+    assert_eq!(tabs.board().game().hints(), 0);
+    tabs.board_mut().undo();
+    assert_eq!(tabs.board().game().history().depth(), 0);
+    tabs.select(0);
+    assert_eq!(tabs.board().game().hints(), 1);
+    assert_eq!(tabs.board().game().history().depth(), 1);
+}
+
+#[test]
+fn the_last_tab_stays_open() {
+    let mut tabs = Tabs::new(board());
+    tabs.open(board());
+    tabs.open(board());
+    tabs.select(1);
+    tabs.close();
+    assert_eq!((tabs.len(), tabs.active()), (2, 1));
+    tabs.close();
+    tabs.close();
+    assert_eq!((tabs.len(), tabs.active()), (1, 0));
+    tabs.cycle(false);
+    assert_eq!(tabs.active(), 0);
+}
@@ -0,0 +1,77 @@
+use sudoku_tui::engine::Game;
+use sudoku_tui::versus::{Mistake, Placement, Versus, CORRECT_POINTS, MISTAKE_POINTS};
+
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+fn game() -> Game {
+    let mut game = Game::from_puzzle(&PUZZLE.parse().unwrap()).unwrap();
+    game.start();
+    game
+}
+
+#[test]
+fn players_take_turns() {
+    let mut game = game();
+    let mut versus = Versus::new();
+    assert_eq!(versus.turn(), 0);
+    assert_eq!(versus.place(&mut game, [0, 2], 4), Placement::Correct);
+    assert_eq!(versus.owner([0, 2]), Some(0));
+    assert_eq!(versus.turn(), 1);
+
+    // wrong digits are not placed, even when they break no rule
+    assert_eq!(versus.place(&mut game, [0, 3], 1), Placement::Mistake);
+    assert_eq!(game.sudoku()[[0, 3]], 0);
+    assert_eq!(versus.owner([0, 3]), None);
+    assert_eq!(
+        versus.last_mistake(),
+        Some(Mistake {
+            player: 1,
+            coord: [0, 3],
+            value: 1
+        })
+    );
+    assert_eq!(versus.turn(), 0);
+
+    // filled cells and givens do not use up a turn
+    assert_eq!(versus.place(&mut game, [0, 2], 4), Placement::Ignored);
+    assert_eq!(versus.place(&mut game, [0, 0], 5), Placement::Ignored);
+    assert_eq!(versus.turn(), 0);
+    assert_eq!(versus.place(&mut game, [0, 3], 6), Placement::Correct);
+    assert_eq!(versus.last_mistake(), None);
+
+    let players = versus.players();
+    assert_eq!(players[0].score, 2 * CORRECT_POINTS);
+    assert_eq!(players[0].correct, 2);
+    assert_eq!(players[1].score, -MISTAKE_POINTS);
+    assert_eq!(players[1].mistakes, 1);
+    assert_eq!(versus.winner(), Some(0));
+}
+
+#[test]
+fn the_full_board_ends_the_game() {
+    let mut game = game();
+    let mut versus = Versus::new();
+    let ans = game.solution();
+    let empty: Vec<[usize; 2]> = (0..81)
+        .map(|x| [x / 9, x % 9])
+        .filter(|&c| game.is_available(c))
+        .collect();
+    // 51 empty cells: the first player places one more digit
+    let (last, rest) = empty.split_last().unwrap();
+    for &[i, j] in rest {
+        assert_eq!(
+            versus.place(&mut game, [i, j], ans[i][j]),
+            Placement::Correct
+        );
+    }
+    assert_eq!(versus.winner(), None);
+    let [i, j] = *last;
+    assert_eq!(
+        versus.place(&mut game, [i, j], ans[i][j]),
+        Placement::Completed
+    );
+    assert!(game.is_finished());
+    assert_eq!(versus.winner(), Some(0));
+    assert_eq!(versus.players()[1].correct, 25);
+}